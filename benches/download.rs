@@ -0,0 +1,43 @@
+//! Times downloading a package held in memory with different `--chunk-size` buffers, to show
+//! how much of a download's time on a fast link is per-chunk overhead. Run with
+//! `cargo bench --bench download`.
+
+mod util;
+
+use jlinkupdate::{download, DownloadOptions, Downloader, Fetch, Result};
+use util::bench;
+
+/// Size of the fake package, about that of a J-Link installer
+const PACKAGE_SIZE: usize = 64 << 20;
+
+/// Serves the same in-memory package for every request, as fast as it can be copied.
+struct MemoryDownloader(&'static [u8]);
+
+impl Downloader for MemoryDownloader {
+    fn fetch(&self, _url: &str, _resume_from: u64) -> Result<Fetch> {
+        Ok(Fetch {
+            resume_from: 0,
+            content_length: Some(self.0.len() as u64),
+            body: Box::new(self.0),
+        })
+    }
+}
+
+fn main() {
+    let downloader = MemoryDownloader(Box::leak(vec![0x5a; PACKAGE_SIZE].into_boxed_slice()));
+    let dir = std::env::temp_dir().join(format!("jlinkupdate-bench-{}", std::process::id()));
+    let path = dir.join("JLink_Linux_V794_x86_64.deb");
+
+    for chunk_size in [1 << 10, 8 << 10, 64 << 10, 1 << 20] {
+        let options = DownloadOptions {
+            show_progress: false,
+            chunk_size,
+            ..DownloadOptions::default()
+        };
+        bench(&format!("download, {} KiB chunks", chunk_size >> 10), 5, || {
+            download(&downloader, "JLink_Linux_V794_x86_64.deb", &path, &options).unwrap();
+            std::fs::remove_file(&path).unwrap();
+        });
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,16 @@
+//! Timing for the benchmarks, which run without the unstable `#[bench]` harness.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Runs `f` `iterations` times after one warm-up run and prints the mean time per run.
+pub fn bench<T>(name: &str, iterations: u32, mut f: impl FnMut() -> T) -> Duration {
+    black_box(f());
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    let mean = start.elapsed() / iterations;
+    println!("{:<40} {:>12.3?}", name, mean);
+    mean
+}
@@ -0,0 +1,40 @@
+//! Times reading the version dropdown and picking the latest version, on a page listing as
+//! many releases as SEGGER's does. Run with `cargo bench --bench versions`.
+
+mod util;
+
+use jlinkupdate::{parse_versions, select_latest_version, version_string_to_number, JLINK};
+use util::bench;
+
+/// A download page shaped like `tests/fixtures/jlink_page.html`, with about 600 J-Link
+/// releases from V6.00 on, newest first.
+fn download_page() -> String {
+    let mut options = String::new();
+    for major in (6..=8).rev() {
+        for minor in (0..=98).rev().step_by(2) {
+            for patch in ["e", "d", "c", "b", "a", ""] {
+                if (minor + patch.len()) % 3 != 0 {
+                    let version = format!("{major}.{minor:02}{patch}");
+                    let value = version.replace('.', "");
+                    options.push_str(&format!(
+                        "      <option value=\"V{value}\">V{version}</option>\n"));
+                }
+            }
+        }
+    }
+    format!("<!DOCTYPE html>\n<html>\n<body>\n  <div id=\"JLink\">\n    \
+             <select class=\"version\">\n{}    </select>\n  </div>\n</body>\n</html>\n",
+            options)
+}
+
+fn main() {
+    let page = download_page();
+    let versions = parse_versions(&page, &JLINK).unwrap();
+    println!("{} versions on a {} byte page", versions.len(), page.len());
+
+    bench("parse_versions", 10, || parse_versions(&page, &JLINK).unwrap());
+    bench("version_string_to_number (all)", 10, || {
+        versions.iter().filter_map(|v| version_string_to_number(v)).count()
+    });
+    bench("select_latest_version", 10, || select_latest_version(&versions).unwrap());
+}
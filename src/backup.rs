@@ -0,0 +1,131 @@
+use crate::error::{Error, Result};
+use crate::installed::{install_roots, newest_install_dir};
+use crate::postinstall::run_privileged;
+use crate::system::SystemInfo;
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where backups of installs under `root` are kept. The name keeps them out of the `JLink*`
+/// globs used to find installs.
+fn backup_dir(root: &Path) -> PathBuf {
+    root.join("backup")
+}
+
+/// Splits a backup directory name like `JLink_V788a-1700000000` into the install directory
+/// name and the time the backup was taken.
+fn split_backup_name(name: &str) -> Option<(&str, u64)> {
+    let (dir_name, timestamp) = name.rsplit_once('-')?;
+    Some((dir_name, timestamp.parse().ok()?))
+}
+
+/// Copies the newest J-Link install aside to a timestamped backup directory. The install is
+/// copied rather than moved so the package manager still finds the files it owns. Only the
+/// new backup is kept, as it's the one --rollback restores and each is a full install. Returns
+/// None if there is nothing to back up or installs don't live in a fixed place on this system.
+pub fn backup_install(system_info: &SystemInfo) -> Result<Option<PathBuf>> {
+    let Some(install_dir) = newest_install_dir(system_info) else {
+        return Ok(None);
+    };
+    let backups = backup_dir(install_dir.parent().unwrap_or(Path::new("/")));
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let name = install_dir.file_name().unwrap_or_default().to_string_lossy();
+    let backup = backups.join(format!("{}-{}", name, timestamp));
+
+    run_privileged(system_info, "mkdir", &["-p", &backups.to_string_lossy()])?;
+    run_privileged(system_info, "cp", &["-a", &install_dir.to_string_lossy(),
+                                        &backup.to_string_lossy()])?;
+    info!("Backed up {} to {}", install_dir.display(), backup.display());
+
+    for old in older_backups(&backups, &backup) {
+        if let Err(e) = run_privileged(system_info, "rm", &["-rf", &old.to_string_lossy()]) {
+            warn!("Could not remove old backup {}: {}", old.display(), e);
+        }
+    }
+    Ok(Some(backup))
+}
+
+/// The backups in `backups` other than `keep`.
+fn older_backups(backups: &Path, keep: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(backups).into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path != keep && path.is_dir())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            split_backup_name(&name).is_some()
+        })
+        .collect()
+}
+
+/// The most recently taken backup, if any.
+pub fn latest_backup(system_info: &SystemInfo) -> Option<PathBuf> {
+    install_roots(system_info).iter()
+        .filter_map(|root| std::fs::read_dir(backup_dir(root)).ok())
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().into_owned();
+            let (_, timestamp) = split_backup_name(&name)?;
+            Some((timestamp, path))
+        })
+        .max_by_key(|(timestamp, _)| *timestamp)
+        .map(|(_, path)| path)
+}
+
+/// Puts `backup` back in place of the install it was taken from, replacing whatever is there
+/// now. Returns the restored install directory.
+pub fn restore_backup(system_info: &SystemInfo, backup: &Path) -> Result<PathBuf> {
+    let name = backup.file_name().unwrap_or_default().to_string_lossy();
+    let (dir_name, _) = split_backup_name(&name)
+        .ok_or_else(|| Error::InvalidOptions(format!("{} is not a backup", backup.display())))?;
+    // Backups sit in a directory next to the install they were taken from
+    let root = backup.parent().and_then(Path::parent)
+        .ok_or_else(|| Error::InvalidOptions(format!("{} is not a backup", backup.display())))?;
+    let target = root.join(dir_name);
+    let target_str = target.to_string_lossy();
+
+    if target.exists() {
+        run_privileged(system_info, "rm", &["-rf", &target_str])?;
+    }
+    run_privileged(system_info, "cp", &["-a", &backup.to_string_lossy(), &target_str])?;
+    info!("Restored {} from {}", target.display(), backup.display());
+
+    if let Some(newest) = newest_install_dir(system_info).filter(|newest| *newest != target) {
+        warn!("{} is newer than the restored install; remove it with your package manager \
+               to use the restored version", newest.display());
+    }
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn backup_names() {
+        assert_eq!(split_backup_name("JLink_V788a-1700000000"), Some(("JLink_V788a", 1700000000)));
+        assert_eq!(split_backup_name("JLink_Linux_V794-x86_64"), None);
+        assert_eq!(split_backup_name("JLink_V788a"), None);
+    }
+
+    #[test]
+    fn finds_older_backups() {
+        let dir = TempDir::new("backups");
+        let dir = dir.path();
+        for name in ["JLink_V788a-1700000000", "JLink_V794-1710000000", "unrelated"] {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        let older = older_backups(dir, &dir.join("JLink_V794-1710000000"));
+
+        assert_eq!(older, [dir.join("JLink_V788a-1700000000")]);
+    }
+}
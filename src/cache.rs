@@ -0,0 +1,162 @@
+use crate::download::sha256_file;
+use crate::error::Result;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VersionCache {
+    url: String,
+    fetched_at: u64,
+    versions: Vec<String>,
+}
+
+/// The directory JLinkUpdate keeps cached data in, if the platform has one.
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("jlinkupdate"))
+}
+
+fn versions_cache_path(product: &str) -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join(format!("{}-versions.json", product)))
+}
+
+/// Where downloaded packages are kept, under the names SEGGER gives them. Those names hold the
+/// product, version, system and arch, so each release's package has its own entry.
+fn packages_dir() -> Option<PathBuf> {
+    cache_dir().map(|dir| dir.join("packages"))
+}
+
+/// Packages kept in the cache per product; older ones are deleted when a new one is stored.
+const CACHED_PACKAGES_PER_PRODUCT: usize = 2;
+
+/// The file holding the SHA256 a cached package had when it was stored.
+fn checksum_path(package: &Path) -> PathBuf {
+    let mut path = package.as_os_str().to_owned();
+    path.push(".sha256");
+    PathBuf::from(path)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// `product`'s version list scraped from `url`, if it was cached less than `max_age` ago.
+pub fn load_cached_versions(product: &str, url: &str, max_age: Duration) -> Option<Vec<String>> {
+    let contents = std::fs::read_to_string(versions_cache_path(product)?).ok()?;
+    let cache: VersionCache = serde_json::from_str(&contents).ok()?;
+    let age = now().saturating_sub(cache.fetched_at);
+    (cache.url == url && age < max_age.as_secs()).then_some(cache.versions)
+}
+
+/// The cached copy of the package called `filename`, if there is one whose contents still
+/// match the checksum recorded when it was stored and, if given, `sha256`. A cached copy that
+/// no longer matches its checksum is removed.
+pub fn load_cached_package(filename: &str, sha256: Option<&str>) -> Option<PathBuf> {
+    let path = packages_dir()?.join(filename);
+    let recorded = std::fs::read_to_string(checksum_path(&path)).ok()?;
+    let actual = sha256_file(&path).ok()?;
+    if !actual.eq_ignore_ascii_case(recorded.trim()) {
+        warn!("Cached {} is corrupt; downloading it again", path.display());
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(checksum_path(&path));
+        return None;
+    }
+    if sha256.is_some_and(|expected| !actual.eq_ignore_ascii_case(expected.trim())) {
+        return None;
+    }
+    Some(path)
+}
+
+/// Copies the downloaded package at `path` into the cache as `filename`, then deletes all but
+/// the newest few cached packages of the same product, as each one is hundreds of MB.
+pub fn store_cached_package(path: &Path, filename: &str) -> Result<()> {
+    let Some(dir) = packages_dir() else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(&dir)?;
+    let cached = dir.join(filename);
+    std::fs::copy(path, &cached)?;
+    std::fs::write(checksum_path(&cached), sha256_file(&cached)?)?;
+    prune_packages(&dir, filename, CACHED_PACKAGES_PER_PRODUCT);
+    Ok(())
+}
+
+/// Deletes all but the `keep` most recently stored packages in `dir` of the product
+/// `filename` is for, going by the product prefix of SEGGER's names, e.g. "JLink".
+fn prune_packages(dir: &Path, filename: &str, keep: usize) {
+    let prefix = filename.split(['_', '-']).next().unwrap_or(filename);
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut packages: Vec<(SystemTime, PathBuf)> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_none_or(|ext| ext != "sha256"))
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.split(['_', '-']).next() == Some(prefix)
+        })
+        .filter_map(|path| Some((std::fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    packages.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    for (_, path) in packages.into_iter().skip(keep) {
+        debug!("Removing old cached package {}", path.display());
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(checksum_path(&path));
+    }
+}
+
+/// Deletes everything cached: version lists and packages. Returns the directory removed, or
+/// None if there was nothing to remove.
+pub fn clear_cache() -> Result<Option<PathBuf>> {
+    match cache_dir() {
+        Some(dir) if dir.exists() => {
+            std::fs::remove_dir_all(&dir)?;
+            Ok(Some(dir))
+        },
+        _ => Ok(None),
+    }
+}
+
+pub fn store_cached_versions(product: &str, url: &str, versions: &[String]) -> Result<()> {
+    let Some(path) = versions_cache_path(product) else {
+        return Ok(());
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let cache = VersionCache {
+        url: url.to_string(),
+        fetched_at: now(),
+        versions: versions.to_vec(),
+    };
+    std::fs::write(path, serde_json::to_string(&cache).expect("cache is always serializable"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs::File;
+
+    #[test]
+    fn keeps_newest_packages_per_product() {
+        let dir = TempDir::new("prune");
+        let dir = dir.path();
+        let names = ["JLink_Linux_V780_x86_64.deb", "JLink_Linux_V788_x86_64.deb",
+                     "JLink_Linux_V794_x86_64.deb", "Ozone_Linux_V320_x86_64.deb"];
+        for (age, name) in names.iter().rev().enumerate() {
+            let path = dir.join(name);
+            let file = File::create(&path).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(age as u64 * 60)).unwrap();
+            std::fs::write(checksum_path(&path), "").unwrap();
+        }
+
+        prune_packages(dir, "JLink_Linux_V794_x86_64.deb", 2);
+        let exists: Vec<bool> = names.iter().map(|name| dir.join(name).exists()).collect();
+
+        assert_eq!(exists, [false, true, true, true]);
+        assert!(!checksum_path(&dir.join(names[0])).exists());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::error::{Error, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Defaults read from the config file. Keys mirror the command-line options, with
+/// underscores in place of dashes, and options given on the command line take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub install: Option<bool>,
+    pub package: Option<String>,
+    pub arch: Option<String>,
+    pub system: Option<String>,
+    pub package_type: Option<String>,
+    pub package_install_cmd: Option<String>,
+    pub prefix: Option<PathBuf>,
+    pub macos_target: Option<PathBuf>,
+    pub filename_template: Option<String>,
+    pub output_dir: Option<PathBuf>,
+    pub keep_download: Option<bool>,
+    pub no_cache: Option<bool>,
+    pub allow_dlopen: Option<bool>,
+    pub smoke_test: Option<bool>,
+    pub ignore_installed: Option<bool>,
+    pub no_fix_deps: Option<bool>,
+    pub resolve_deps: Option<bool>,
+    pub retries: Option<u32>,
+    pub max_rate: Option<String>,
+    pub timeout: Option<u64>,
+    pub connect_timeout: Option<u64>,
+    pub proxy: Option<String>,
+    pub base_url: Option<String>,
+    pub mirrors: Option<Vec<String>>,
+    pub user_agent: Option<String>,
+    pub notify: Option<bool>,
+    pub include_beta: Option<bool>,
+    pub yes: Option<bool>,
+    pub accept_license: Option<bool>,
+    pub silent: Option<bool>,
+    pub silent_args: Option<String>,
+    pub install_args: Option<Vec<String>>,
+    pub format: Option<String>,
+    pub color: Option<String>,
+    pub max_age: Option<String>,
+}
+
+/// `~/.config/jlinkupdate/config.toml`, or the platform's equivalent.
+pub fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("jlinkupdate").join("config.toml"))
+}
+
+/// Reads the config file at `path`; a missing file gives the empty config.
+pub fn load_config(path: &Path) -> Result<Config> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(e.into()),
+    };
+    toml::from_str(&contents)
+        .map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_keys_and_rejects_unknown() {
+        let config: Config = toml::from_str("arch = \"arm64\"\nretries = 5\n").unwrap();
+        assert_eq!(config.arch.as_deref(), Some("arm64"));
+        assert_eq!(config.retries, Some(5));
+        assert!(toml::from_str::<Config>("archh = \"arm64\"\n").is_err());
+    }
+}
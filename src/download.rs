@@ -0,0 +1,638 @@
+use crate::error::{Error, Result};
+use crate::product::Product;
+use crate::source::{Downloader, Fetch, VersionSource};
+use indicatif::{ProgressBar, ProgressStyle};
+use log::{debug, info, warn};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{HeaderMap, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use reqwest::redirect::Policy;
+use reqwest::StatusCode;
+use scraper::{ElementRef, Html, Selector};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long to wait before retry number `attempt` + 1: doubling from 1s, up to 32s, so a
+/// large --retries keeps retrying rather than sleeping for days.
+fn retry_delay(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt.min(5))
+}
+
+/// What to do after an attempt at sending a request.
+pub(crate) enum Attempt<R> {
+    /// Stop with this result
+    Done(Result<R>),
+    /// Wait this long, then send the request again
+    Retry(Duration),
+}
+
+/// Decides whether attempt number `attempt` + 1 of `retries` + 1 at a request succeeded, failed
+/// for good or should be retried, for `send_with_retry` and the async client alike. `status`
+/// gets a response's status.
+pub(crate) fn judge_attempt<R>(sent: reqwest::Result<R>, status: impl Fn(&R) -> StatusCode,
+                               attempt: u32, retries: u32) -> Attempt<R> {
+    let error = match sent {
+        Ok(response) if status(&response).is_server_error() && attempt < retries => {
+            Error::Status(status(&response), None)
+        },
+        Ok(response) => return Attempt::Done(Ok(response)),
+        Err(e) if e.is_builder() || e.is_status() => return Attempt::Done(Err(e.into())),
+        Err(e) => e.into(),
+    };
+
+    if attempt >= retries {
+        return Attempt::Done(Err(error));
+    }
+    let delay = retry_delay(attempt);
+    warn!("Request failed ({}); retrying in {}s (attempt {}/{})",
+          error, delay.as_secs(), attempt + 1, retries);
+    Attempt::Retry(delay)
+}
+
+/// Sends a request, retrying connection errors and 5xx responses with exponential backoff.
+/// Once the retries run out, a 5xx response is returned like any other, so the caller can
+/// report what its body says; see `response_error`.
+pub fn send_with_retry(retries: u32, request: impl Fn() -> RequestBuilder)
+    -> Result<Response> {
+    let mut attempt = 0;
+    loop {
+        match judge_attempt(request().send(), Response::status, attempt, retries) {
+            Attempt::Done(result) => return result,
+            Attempt::Retry(delay) => std::thread::sleep(delay),
+        }
+        attempt += 1;
+    }
+}
+
+/// Redirects followed before a request gives up.
+const MAX_REDIRECTS: usize = 10;
+
+/// The redirect policy for the client used with `download`. SEGGER answers the license POST
+/// with a redirect to its CDN: on a 301-303 reqwest follows with a GET and drops the form, which
+/// the CDN doesn't need, while on a 307/308 it resends the POST with the form attached.
+pub fn redirect_policy() -> Policy {
+    Policy::custom(|attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error(format!("more than {} redirects", MAX_REDIRECTS))
+        } else {
+            debug!("Redirected ({}) to {}", attempt.status(), attempt.url());
+            attempt.follow()
+        }
+    })
+}
+
+fn selector(css: &str) -> Result<Selector> {
+    Selector::parse(css)
+        .map_err(|e| Error::PageLayout(format!("invalid selector '{}': {}", css, e)))
+}
+
+/// Whether `element` sits inside the page section with id `section`.
+fn in_section(element: &ElementRef, section: &str) -> bool {
+    element.ancestors()
+        .filter_map(|node| node.value().as_element())
+        .filter_map(|ancestor| ancestor.id())
+        .any(|id| id.eq_ignore_ascii_case(section))
+}
+
+/// Reads `product`'s version dropdown from the HTML of the SEGGER download page, in page
+/// order.
+pub fn parse_versions(html: &str, product: &Product) -> Result<Vec<String>> {
+    let document = Html::parse_document(html);
+    let version_select = document.select(&selector("select.version")?)
+        .find(|select| product.section.is_none_or(|section| in_section(select, section)))
+        .ok_or_else(|| Error::PageLayout(format!("could not find {} version dropdown",
+                                                 product.title)))?;
+
+    let versions: Vec<String> = version_select.select(&selector("option")?)
+        .filter_map(|option| option.text().next())
+        .map(|text| text.trim().to_string())
+        .collect();
+    if versions.is_empty() {
+        return Err(Error::PageLayout("version dropdown has no options".to_string()));
+    }
+    Ok(versions)
+}
+
+/// Scrapes versions from and downloads packages off SEGGER's site with reqwest.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+    page_url: String,
+    retries: u32,
+}
+
+impl HttpClient {
+    /// Uses `client` with the download page at `page_url`, retrying failed requests `retries`
+    /// times.
+    pub fn new(client: Client, page_url: &str, retries: u32) -> Self {
+        HttpClient {
+            client,
+            page_url: page_url.to_string(),
+            retries,
+        }
+    }
+
+    fn request_download(&self, url: &str, resume_from: u64) -> Result<Response> {
+        send_with_retry(self.retries, || {
+            let request = self.client.post(url)
+                .form(&[(LICENSE_FIELD, "accepted")]);
+            if resume_from > 0 {
+                request.header(RANGE, format!("bytes={}-", resume_from))
+            } else {
+                request
+            }
+        })
+    }
+}
+
+impl VersionSource for HttpClient {
+    fn versions(&self, product: &Product) -> Result<Vec<String>> {
+        let response = send_with_retry(self.retries, || self.client.get(&self.page_url))?;
+        if !response.status().is_success() {
+            return Err(response_error(response));
+        }
+        parse_versions(&response.text()?, product)
+    }
+}
+
+impl Downloader for HttpClient {
+    fn fetch(&self, url: &str, resume_from: u64) -> Result<Fetch> {
+        let mut response = self.request_download(url, resume_from)?;
+        let mut resumed = false;
+        if resume_from > 0 {
+            let content_range = response.headers().get(CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("");
+            if response.status() == StatusCode::PARTIAL_CONTENT
+                && content_range.starts_with(&format!("bytes {}-", resume_from)) {
+                resumed = true;
+            } else if matches!(response.status(),
+                               StatusCode::PARTIAL_CONTENT | StatusCode::RANGE_NOT_SATISFIABLE) {
+                // The server can't continue from the partial file, so start over without a range
+                response = self.request_download(url, 0)?;
+            }
+        }
+
+        check_final_url(url, response.url().as_str(), &self.page_url)?;
+        if response.status() != 200 && !resumed {
+            let status = response.status();
+            return Err(download_status_error(url, status, &response.text().unwrap_or_default()));
+        }
+        if is_html(response.headers()) {
+            return Err(html_download_error(url, &response.text()?));
+        }
+
+        Ok(Fetch {
+            resume_from: if resumed { resume_from } else { 0 },
+            content_length: response.content_length(),
+            body: Box::new(response),
+        })
+    }
+}
+
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Expected SHA256 digest (hex) of the complete file
+    pub sha256: Option<String>,
+    /// Draw a progress bar on stderr
+    pub show_progress: bool,
+    /// Cap on the download rate in bytes per second
+    pub max_rate: Option<u64>,
+    /// Size in bytes of the buffer the response is read into
+    pub chunk_size: usize,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        DownloadOptions {
+            sha256: None,
+            show_progress: true,
+            max_rate: None,
+            chunk_size: 64 * 1024,
+        }
+    }
+}
+
+/// Parses a byte count with an optional binary suffix, e.g. "500k" or "2M".
+pub fn parse_byte_size(size: &str) -> std::result::Result<u64, String> {
+    let size = size.trim();
+    let (digits, multiplier) = match size.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&size[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&size[..i], 1 << 20),
+        Some((i, 'g' | 'G')) => (&size[..i], 1 << 30),
+        _ => (size, 1),
+    };
+    digits.trim().parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .filter(|&n| n > 0)
+        .ok_or_else(|| format!("'{}' is not a size like 65536, 500k or 2M", size))
+}
+
+/// A writer that sleeps as needed to keep the average write rate under `max_rate` bytes
+/// per second.
+struct Throttle<W> {
+    inner: W,
+    max_rate: Option<u64>,
+    start: Instant,
+    written: u64,
+}
+
+impl<W: Write> Write for Throttle<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if let Some(max_rate) = self.max_rate {
+            self.written += n as u64;
+            let due = Duration::from_secs_f64(self.written as f64 / max_rate as f64);
+            if let Some(ahead) = due.checked_sub(self.start.elapsed()) {
+                std::thread::sleep(ahead);
+            }
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The file a download is written to until it completes and passes verification.
+pub fn partial_path(download_path: &Path) -> PathBuf {
+    let mut path = download_path.as_os_str().to_owned();
+    path.push(".part");
+    PathBuf::from(path)
+}
+
+/// Downloads `file_url` to `download_path` via a `.part` file, resuming one left by an
+/// interrupted run, and verifies the result against the expected SHA256 or, failing that,
+/// the advertised length. `download_path` only appears once the download is complete.
+pub fn download(downloader: &impl Downloader, file_url: &str, download_path: &Path,
+                options: &DownloadOptions) -> Result<()> {
+    let part_path = partial_path(download_path);
+    let existing_size = std::fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+
+    debug!("Requesting {}", file_url);
+    let Fetch { resume_from, content_length, body: mut response } =
+        downloader.fetch(file_url, existing_size)?;
+    if resume_from > 0 {
+        info!("Resuming download at {} bytes", resume_from);
+    }
+
+    let pb = match (options.show_progress, content_length) {
+        (false, _) => ProgressBar::hidden(),
+        (true, Some(length)) => {
+            ProgressBar::new(resume_from + length).with_style(ProgressStyle::default_bar()
+                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+                .unwrap())
+        },
+        // Without a length there's nothing to fill a bar against, so just count bytes
+        (true, None) => {
+            ProgressBar::new_spinner().with_style(ProgressStyle::default_spinner()
+                .template("{spinner:.green} [{elapsed_precise}] {bytes} ({bytes_per_sec})")
+                .unwrap())
+        },
+    };
+    pb.set_position(resume_from);
+
+    if let Some(parent) = download_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = if resume_from > 0 {
+        std::fs::OpenOptions::new().append(true).open(&part_path)?
+    } else {
+        File::create(&part_path)?
+    };
+    // An interrupted copy leaves the .part file in place so the next run can resume it
+    let mut file = Throttle {
+        inner: file,
+        max_rate: options.max_rate,
+        start: Instant::now(),
+        written: 0,
+    };
+    let mut buf = vec![0; options.chunk_size.max(1)];
+    // Bytes actually received; the bar stops at the advertised length should the server
+    // send more than it said it would
+    let mut written = 0;
+    loop {
+        let n = match response.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        file.write_all(&buf[..n])?;
+        written += n as u64;
+        let position = resume_from + written;
+        pb.set_position(pb.length().map_or(position, |length| position.min(length)));
+    }
+    file.flush()?;
+
+    pb.finish_with_message("Download completed");
+
+    if let Err(e) = verify_download(&part_path, options, written, content_length) {
+        std::fs::remove_file(&part_path)?;
+        return Err(e);
+    }
+    std::fs::rename(&part_path, download_path)?;
+
+    Ok(())
+}
+
+/// Name of the form field the download page's license dialog posts.
+pub(crate) const LICENSE_FIELD: &str = "accept_license_agreement";
+
+/// Whether `html` is the license dialog itself, which SEGGER sends back instead of the
+/// package when the acceptance it was sent isn't the one it expects.
+fn is_license_page(html: &str) -> bool {
+    html.contains(LICENSE_FIELD)
+}
+
+/// Whether a response with `headers` is an HTML page rather than a package.
+pub(crate) fn is_html(headers: &HeaderMap) -> bool {
+    headers.get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|content_type| content_type.trim().to_lowercase().starts_with("text/html"))
+}
+
+/// Checks that a download of `url` that ended up at `final_url` wasn't sent back to the
+/// download page at `page_url`, which means the license acceptance wasn't taken.
+pub(crate) fn check_final_url(url: &str, final_url: &str, page_url: &str) -> Result<()> {
+    let final_url = final_url.trim_end_matches('/');
+    if final_url != url && final_url == page_url.trim_end_matches('/') {
+        return Err(Error::LicenseRejected(format!("{} redirected to the download page", url)));
+    }
+    Ok(())
+}
+
+/// The error for a download of `url` answered with `status` and `body` instead of the package.
+pub(crate) fn download_status_error(url: &str, status: StatusCode, body: &str) -> Error {
+    if status.is_client_error() && is_license_page(body) {
+        return Error::LicenseRejected(format!("{} returned {} with the license form", url, status));
+    }
+    status_error(status, body)
+}
+
+/// The error for a download of `url` answered with the HTML page `html` instead of the
+/// package.
+pub(crate) fn html_download_error(url: &str, html: &str) -> Error {
+    if is_license_page(html) {
+        return Error::LicenseRejected(format!("{} returned the license form", url));
+    }
+    Error::Download(format!("server sent an HTML page instead of {}: {}", url, page_snippet(html)))
+}
+
+/// An `Error::Status` for `status`, with the gist of the response `body` SEGGER sometimes
+/// explains the failure in.
+pub(crate) fn status_error(status: StatusCode, body: &str) -> Error {
+    let snippet = page_snippet(body);
+    Error::Status(status, Some(snippet).filter(|snippet| !snippet.is_empty()))
+}
+
+/// `status_error` for an unsuccessful `response`, reading its body.
+pub(crate) fn response_error(response: Response) -> Error {
+    let status = response.status();
+    status_error(status, &response.text().unwrap_or_default())
+}
+
+/// The start of the visible text of an HTML page, for error messages.
+fn page_snippet(html: &str) -> String {
+    const SNIPPET_LEN: usize = 200;
+    let document = Html::parse_document(html);
+    let text = document.root_element().text().collect::<Vec<_>>().join(" ");
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut snippet = words.join(" ");
+    if let Some((end, _)) = snippet.char_indices().nth(SNIPPET_LEN) {
+        snippet.truncate(end);
+        snippet.push_str("...");
+    }
+    snippet
+}
+
+pub(crate) fn verify_download(path: &Path, options: &DownloadOptions, written: u64,
+                              content_length: Option<u64>) -> Result<()> {
+    if let Some(expected) = content_length {
+        if written > expected {
+            warn!("Downloaded {} bytes but the server reported only {}", written, expected);
+        } else if written < expected && options.sha256.is_none() {
+            return Err(Error::Download(format!("downloaded {} bytes but server reported {} bytes",
+                                               written, expected)));
+        }
+    }
+    if let Some(expected) = &options.sha256 {
+        check_sha256(path, expected)?;
+    }
+    Ok(())
+}
+
+fn check_sha256(path: &Path, expected: &str) -> Result<()> {
+    let actual = sha256_file(path)?;
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(Error::Download(format!("SHA256 mismatch for {}: expected {}, got {}",
+                                           path.display(), expected, actual)));
+    }
+    info!("SHA256 verified: {}", actual);
+    Ok(())
+}
+
+/// Checks a package already on disk, like one kept from an earlier download, against the
+/// expected SHA256 digest and size. Unlike a download, a file of the wrong size always fails.
+pub fn verify_package(path: &Path, sha256: Option<&str>, size: Option<u64>) -> Result<()> {
+    if let Some(expected) = size {
+        let actual = std::fs::metadata(path)?.len();
+        if actual != expected {
+            return Err(Error::Download(format!("{} is {} bytes but should be {} bytes",
+                                               path.display(), actual, expected)));
+        }
+        info!("Size verified: {} bytes", actual);
+    }
+    if let Some(expected) = sha256 {
+        check_sha256(path, expected)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn byte_sizes() {
+        assert_eq!(parse_byte_size("65536"), Ok(65536));
+        assert_eq!(parse_byte_size("500k"), Ok(500 * 1024));
+        assert_eq!(parse_byte_size("2M"), Ok(2 * 1024 * 1024));
+        assert!(parse_byte_size("fast").is_err());
+        assert!(parse_byte_size("0").is_err());
+    }
+
+    /// Serves `contents` from memory, resuming wherever asked, and claims the body is
+    /// `advertised_length` bytes long if set.
+    struct FakeDownloader {
+        contents: &'static [u8],
+        advertised_length: Option<u64>,
+    }
+
+    impl Downloader for FakeDownloader {
+        fn fetch(&self, _url: &str, resume_from: u64) -> Result<Fetch> {
+            let body = &self.contents[resume_from as usize..];
+            Ok(Fetch {
+                resume_from,
+                content_length: self.advertised_length.or(Some(body.len() as u64)),
+                body: Box::new(body),
+            })
+        }
+    }
+
+    #[test]
+    fn parses_product_version_dropdowns() {
+        let page = r#"<html><body>
+            <div id="JLink"><select class="version"><option>V7.94</option><option> V7.92b </option></select></div>
+            <div id="Ozone"><select class="version"><option>V3.30</option></select></div>
+        </body></html>"#;
+        assert_eq!(parse_versions(page, &crate::product::JLINK).unwrap(), ["V7.94", "V7.92b"]);
+        assert_eq!(parse_versions(page, &crate::product::OZONE).unwrap(), ["V3.30"]);
+        assert!(parse_versions("<html></html>", &crate::product::JLINK).is_err());
+    }
+
+    #[test]
+    fn resumes_partial_download() {
+        let dir = TempDir::new("resume");
+        let path = dir.path().join("JLink_Linux_V794_x86_64.deb");
+        std::fs::write(partial_path(&path), "package").unwrap();
+
+        let downloader = FakeDownloader { contents: b"package contents", advertised_length: None };
+        let options = DownloadOptions { show_progress: false, ..DownloadOptions::default() };
+        let result = download(&downloader, "JLink_Linux_V794_x86_64.deb", &path, &options);
+
+        result.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "package contents");
+    }
+
+    #[test]
+    fn checks_length_against_advertised() {
+        let dir = TempDir::new("length");
+        let path = dir.path().join("JLink_Linux_V794_x86_64.deb");
+        let options = DownloadOptions { show_progress: false, ..DownloadOptions::default() };
+        let longer = FakeDownloader { contents: b"package contents", advertised_length: Some(7) };
+        let longer_result = download(&longer, "JLink_Linux_V794_x86_64.deb", &path, &options);
+        let shorter = FakeDownloader { contents: b"package", advertised_length: Some(16) };
+        let shorter_result = download(&shorter, "JLink_Linux_V794_x86_64.deb", &path, &options);
+
+        longer_result.unwrap();
+        assert!(shorter_result.is_err());
+    }
+
+    #[test]
+    fn verifies_existing_package() {
+        let dir = TempDir::new("verify");
+        let path = dir.path().join("JLink_Linux_V794_x86_64.deb");
+        std::fs::write(&path, "package").unwrap();
+        let sha256 = sha256_file(&path).unwrap();
+        let matching = verify_package(&path, Some(&sha256), Some(7));
+        let longer = verify_package(&path, None, Some(6));
+        let corrupt = verify_package(&path, Some(&"0".repeat(64)), None);
+
+        matching.unwrap();
+        assert!(longer.is_err());
+        assert!(corrupt.is_err());
+    }
+
+    #[test]
+    fn caps_retry_delay() {
+        assert_eq!(retry_delay(0), Duration::from_secs(1));
+        assert_eq!(retry_delay(3), Duration::from_secs(8));
+        assert_eq!(retry_delay(5), Duration::from_secs(32));
+        assert_eq!(retry_delay(64), Duration::from_secs(32));
+    }
+
+    #[test]
+    fn reports_body_of_server_error() {
+        let mut server = mockito::Server::new();
+        let page = server.mock("GET", "/")
+            .with_status(503)
+            .with_body("<html><body><p>Down for maintenance until 14:00</p></body></html>")
+            .expect(2)
+            .create();
+
+        let client = HttpClient::new(Client::new(), &server.url(), 1);
+        let error = client.versions(&crate::product::JLINK).unwrap_err();
+
+        page.assert();
+        assert!(matches!(error, Error::Status(StatusCode::SERVICE_UNAVAILABLE, Some(_))));
+        assert!(error.to_string().ends_with(": Down for maintenance until 14:00"), "{}", error);
+    }
+
+    #[test]
+    fn follows_redirect_from_license_post() {
+        let mut server = mockito::Server::new();
+        let post = server.mock("POST", "/JLink_Linux_V794_x86_64.deb")
+            .match_body("accept_license_agreement=accepted")
+            .with_status(302)
+            .with_header("location", "/cdn/JLink_Linux_V794_x86_64.deb")
+            .create();
+        let get = server.mock("GET", "/cdn/JLink_Linux_V794_x86_64.deb")
+            .with_body("package contents")
+            .create();
+
+        let client = Client::builder().redirect(redirect_policy()).build().unwrap();
+        let client = HttpClient::new(client, &server.url(), 0);
+        let dir = TempDir::new("redirect");
+        let path = dir.path().join("JLink_Linux_V794_x86_64.deb");
+        let options = DownloadOptions { show_progress: false, ..DownloadOptions::default() };
+        let url = format!("{}/JLink_Linux_V794_x86_64.deb", server.url());
+        let result = download(&client, &url, &path, &options);
+
+        result.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "package contents");
+        post.assert();
+        get.assert();
+    }
+
+    #[test]
+    fn rejects_html_instead_of_package() {
+        let mut server = mockito::Server::new();
+        server.mock("POST", "/JLink_Linux_V794_x86_64.deb")
+            .with_header("content-type", "text/html; charset=utf-8")
+            .with_body("<html><body><h1>Please accept   the license</h1></body></html>")
+            .create();
+
+        let dir = TempDir::new("html");
+        let path = dir.path().join("JLink_Linux_V794_x86_64.deb");
+        let options = DownloadOptions { show_progress: false, ..DownloadOptions::default() };
+        let url = format!("{}/JLink_Linux_V794_x86_64.deb", server.url());
+        let result = download(&HttpClient::new(Client::new(), &server.url(), 0), &url, &path, &options);
+
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("Please accept the license"), "{}", message);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn detects_rejected_license() {
+        let mut server = mockito::Server::new();
+        server.mock("POST", "/JLink_Linux_V794_x86_64.deb")
+            .with_header("content-type", "text/html")
+            .with_body("<form><input type=\"checkbox\" name=\"accept_license_agreement\"></form>")
+            .create();
+        server.mock("POST", "/JLink_Linux_V795_x86_64.deb")
+            .with_status(303)
+            .with_header("location", "/")
+            .create();
+        server.mock("GET", "/").with_body("<html></html>").create();
+
+        let client = HttpClient::new(Client::new(), &format!("{}/", server.url()), 0);
+        for version in ["V794", "V795"] {
+            let url = format!("{}/JLink_Linux_{}_x86_64.deb", server.url(), version);
+            let result = client.fetch(&url, 0);
+            assert!(matches!(result, Err(Error::LicenseRejected(_))), "{}", version);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use reqwest::StatusCode;
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("network error: {0}")]
+    Network(#[from] reqwest::Error),
+
+    /// The status and, when the server sent one, the start of the response body's text
+    #[error("server returned status {0}{body}",
+            body = .1.as_ref().map(|body| format!(": {}", body)).unwrap_or_default())]
+    Status(StatusCode, Option<String>),
+
+    #[error("SEGGER download page layout changed; {0}")]
+    PageLayout(String),
+
+    #[error("could not parse version '{0}'")]
+    VersionParse(String),
+
+    #[error("version {version} is not available; closest available versions: {}", closest.join(", "))]
+    VersionNotFound { version: String, closest: Vec<String> },
+
+    #[error("download failed: {0}")]
+    Download(String),
+
+    #[error("SEGGER license acceptance failed; the form may have changed ({0})")]
+    LicenseRejected(String),
+
+    #[error("installation failed: {0}")]
+    Install(String),
+
+    #[error("invalid config: {0}")]
+    Config(String),
+
+    #[error("invalid options: {0}")]
+    InvalidOptions(String),
+
+    #[error("aborted: {0}")]
+    Aborted(String),
+
+    #[error("another instance is running (lock file {})", .0.display())]
+    Locked(PathBuf),
+
+    #[error("could not open lock file {}: {}", .0.display(), .1)]
+    LockFile(PathBuf, std::io::Error),
+
+    #[error("could not show notification: {0}")]
+    Notification(String),
+
+    #[error("unsupported system '{0}'")]
+    UnsupportedSystem(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,282 @@
+use crate::error::{Error, Result};
+use crate::installed::command_output;
+use crate::postinstall::run_privileged;
+use crate::system::{check_sudo, find_in_path, SystemInfo};
+use log::debug;
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+
+#[derive(Debug, Clone)]
+pub struct InstallOptions {
+    /// Run `apt-get install -f` after a dpkg-based install to pull in dependencies
+    pub fix_deps: bool,
+    /// Arguments passed to the Windows installer, e.g. `-Silent=1` for an unattended install
+    pub windows_args: Vec<String>,
+    /// Extra arguments appended to the install command after the package path
+    pub extra_args: Vec<String>,
+    /// Ask the package manager to reinstall the package; only set when that version is installed
+    pub reinstall: bool,
+    /// Let the package manager replace the installed version with an older one
+    pub downgrade: bool,
+}
+
+impl Default for InstallOptions {
+    fn default() -> Self {
+        InstallOptions {
+            fix_deps: true,
+            windows_args: Vec::new(),
+            extra_args: Vec::new(),
+            reinstall: false,
+            downgrade: false,
+        }
+    }
+}
+
+/// Adjusts a package manager command so it reinstalls a version that is already installed.
+/// dpkg and the macOS and Windows installers always reinstall, so they're left alone.
+fn add_reinstall_flag(command: &mut Vec<String>) {
+    let Some(program) = command.iter().position(|arg| arg != "sudo") else {
+        return;
+    };
+    let install = command.iter().position(|arg| arg == "install");
+    match (command[program].as_str(), install) {
+        ("apt-get" | "apt", Some(install)) => command.insert(install + 1, "--reinstall".to_string()),
+        ("dnf" | "yum", Some(install)) => command[install] = "reinstall".to_string(),
+        ("zypper", Some(install)) => command.insert(install + 1, "--force".to_string()),
+        ("rpm", _) => command.insert(program + 1, "--replacepkgs".to_string()),
+        _ => {},
+    }
+}
+
+/// Adjusts a package manager command so it accepts a package older than the installed one.
+/// dpkg and the macOS and Windows installers downgrade without being asked.
+fn add_downgrade_flag(command: &mut Vec<String>) {
+    let Some(program) = command.iter().position(|arg| arg != "sudo") else {
+        return;
+    };
+    let install = command.iter().position(|arg| arg == "install");
+    match (command[program].as_str(), install) {
+        ("apt-get" | "apt", Some(install)) => {
+            command.insert(install + 1, "--allow-downgrades".to_string())
+        },
+        ("dnf" | "yum", Some(install)) => command[install] = "downgrade".to_string(),
+        ("zypper", Some(install)) => command.insert(install + 1, "--oldpackage".to_string()),
+        ("rpm", _) => command.insert(program + 1, "--oldpackage".to_string()),
+        _ => {},
+    }
+}
+
+/// Checks that the install command's program exists, so a missing package manager is found
+/// before the package is downloaded rather than after.
+pub fn check_install_command(system_info: &SystemInfo) -> Result<()> {
+    // On Windows the downloaded installer is the program
+    if system_info.system == "Windows" {
+        return Ok(());
+    }
+    let command: Vec<String> = system_info.package_install_cmd.split_whitespace()
+        .map(str::to_string)
+        .collect();
+    check_sudo(&command)?;
+    let Some(program) = command.iter().find(|arg| *arg != "sudo") else {
+        return Err(Error::Install(format!(
+            "no install command for {} packages on {}; pass --package-install-cmd",
+            system_info.package_type, system_info.system)));
+    };
+    let found = if program.contains(std::path::MAIN_SEPARATOR) {
+        Path::new(program).is_file()
+    } else {
+        find_in_path(program).is_some()
+    };
+    if !found {
+        return Err(Error::Install(format!(
+            "package manager `{}` not found; install it or pass --package-install-cmd", program)));
+    }
+    Ok(())
+}
+
+/// The program and arguments that install the package at `package_path`.
+pub fn install_command(system_info: &SystemInfo, package_path: &Path, options: &InstallOptions)
+    -> Result<Vec<String>> {
+    let package = std::path::absolute(package_path)?.display().to_string();
+    let mut command = if cfg!(target_os = "windows") && system_info.package_type == "msi" {
+        ["msiexec", "/i", &package, "/quiet"].map(str::to_string).to_vec()
+    } else if cfg!(target_os = "windows") {
+        let mut command = vec![package];
+        command.extend(options.windows_args.iter().cloned());
+        command
+    } else {
+        let mut command: Vec<String> = system_info.package_install_cmd.split_whitespace()
+            .map(str::to_string)
+            .collect();
+        if command.is_empty() {
+            return Err(Error::Install(format!(
+                "no install command for {} packages on {}; pass --package-install-cmd",
+                system_info.package_type, system_info.system)));
+        }
+        if options.reinstall {
+            add_reinstall_flag(&mut command);
+        } else if options.downgrade {
+            add_downgrade_flag(&mut command);
+        }
+        let installer = command.iter().any(|arg| arg == "installer");
+        command.push(package);
+        if installer && system_info.package_type == "pkg" {
+            command.push("-target".to_string());
+            command.push(system_info.macos_target.display().to_string());
+        }
+        command
+    };
+    command.extend(options.extra_args.iter().cloned());
+    Ok(command)
+}
+
+/// Whether dpkg reports the package in the .deb at `package_path` as fully installed.
+fn is_deb_installed(package_path: &Path) -> bool {
+    let path = package_path.to_string_lossy();
+    let Some(name) = command_output("dpkg-deb", &["-f", &path, "Package"]) else {
+        return false;
+    };
+    command_output("dpkg-query", &["-W", "-f=${Status}", name.trim()])
+        .is_some_and(|status| status.trim() == "install ok installed")
+}
+
+/// Runs an install `command`. Not being able to start it is an install failure too.
+fn run_installer<S: AsRef<str>>(command: &[S]) -> Result<ExitStatus> {
+    let program = command[0].as_ref();
+    Command::new(program)
+        .args(command[1..].iter().map(AsRef::as_ref))
+        .status()
+        .map_err(|e| Error::Install(format!("could not run {}: {}", program, e)))
+}
+
+/// Installs the package at `package_path` using the system's install command.
+pub fn install(system_info: &SystemInfo, package_path: &Path, options: &InstallOptions)
+    -> Result<()> {
+    let command = install_command(system_info, package_path, options)?;
+    check_sudo(&command)?;
+    if system_info.package_type == "tgz" {
+        run_privileged(system_info, "mkdir", &["-p", &system_info.prefix.to_string_lossy()])?;
+    }
+    debug!("Running {}", command.join(" "));
+    let mut status = run_installer(&command)?;
+
+    // dpkg exits non-zero when dependencies are missing, so the fix-up pass runs regardless.
+    // dpkg can also fail for reasons the fix-up can't help with, like a corrupt package, so
+    // its failure only counts as fixed if the package ends up installed.
+    let install_cmd: Vec<&str> = system_info.package_install_cmd.split_whitespace().collect();
+    if install_cmd.contains(&"dpkg") && options.fix_deps {
+        let mut fix_cmd = vec!["apt-get", "install", "-f", "-y"];
+        if install_cmd.first() == Some(&"sudo") {
+            fix_cmd.insert(0, "sudo");
+        }
+        let fix_status = run_installer(&fix_cmd)?;
+        if !fix_status.success() {
+            return Err(Error::Install(format!("apt-get install -f exited with {}", fix_status)));
+        }
+        if !status.success() && is_deb_installed(package_path) {
+            debug!("apt-get install -f completed the install dpkg couldn't finish");
+            status = fix_status;
+        }
+    }
+
+    if !status.success() {
+        return Err(Error::Install(format!("installer exited with {}", status)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::DEFAULT_FILENAME_TEMPLATE;
+
+    fn reinstall_command(install_cmd: &str) -> String {
+        let mut command: Vec<String> = install_cmd.split_whitespace().map(str::to_string).collect();
+        add_reinstall_flag(&mut command);
+        command.join(" ")
+    }
+
+    fn downgrade_command(install_cmd: &str) -> String {
+        let mut command: Vec<String> = install_cmd.split_whitespace().map(str::to_string).collect();
+        add_downgrade_flag(&mut command);
+        command.join(" ")
+    }
+
+    fn system_info(install_cmd: &str) -> SystemInfo {
+        SystemInfo {
+            arch: "x86_64".to_string(),
+            system: "Linux".to_string(),
+            package_type: "deb".to_string(),
+            package_install_cmd: install_cmd.to_string(),
+            fallback_archs: Vec::new(),
+            prefix: "/opt/SEGGER".into(),
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+            macos_target: "/".into(),
+        }
+    }
+
+    #[test]
+    fn checks_package_manager_exists() {
+        assert!(check_install_command(&system_info("sh -c")).is_ok());
+        assert!(matches!(check_install_command(&system_info("no-such-package-manager -i")),
+                         Err(Error::Install(_))));
+        assert!(matches!(check_install_command(&system_info("/no/such/dpkg -i")),
+                         Err(Error::Install(_))));
+        assert!(matches!(check_install_command(&system_info("")), Err(Error::Install(_))));
+    }
+
+    #[test]
+    fn missing_installer_is_an_install_failure() {
+        let command = ["/no/such/installer", "package.deb"];
+        assert!(matches!(run_installer(&command), Err(Error::Install(_))));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn installs_msi_with_msiexec() {
+        let system_info = SystemInfo {
+            system: "Windows".to_string(),
+            package_type: "msi".to_string(),
+            ..system_info("")
+        };
+        let command = install_command(&system_info, Path::new("C:\\JLink_Windows_V794_x86_64.msi"),
+                                      &InstallOptions::default()).unwrap();
+        assert_eq!(command, ["msiexec", "/i", "C:\\JLink_Windows_V794_x86_64.msi", "/quiet"]);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn passes_macos_target_after_package() {
+        let system_info = SystemInfo {
+            system: "MacOSX".to_string(),
+            package_type: "pkg".to_string(),
+            macos_target: "/Volumes/SEGGER Tools".into(),
+            ..system_info("sudo installer -pkg")
+        };
+        let command = install_command(&system_info, Path::new("/tmp/JLink.pkg"),
+                                      &InstallOptions::default()).unwrap();
+        assert_eq!(command, ["sudo", "installer", "-pkg", "/tmp/JLink.pkg", "-target",
+                             "/Volumes/SEGGER Tools"]);
+    }
+
+    #[test]
+    fn reinstall_flags() {
+        assert_eq!(reinstall_command("sudo apt-get install -y"), "sudo apt-get install --reinstall -y");
+        assert_eq!(reinstall_command("sudo dnf install -y"), "sudo dnf reinstall -y");
+        assert_eq!(reinstall_command("sudo zypper --non-interactive install"),
+                   "sudo zypper --non-interactive install --force");
+        assert_eq!(reinstall_command("sudo rpm -U"), "sudo rpm --replacepkgs -U");
+        assert_eq!(reinstall_command("sudo dpkg -i"), "sudo dpkg -i");
+    }
+
+    #[test]
+    fn downgrade_flags() {
+        assert_eq!(downgrade_command("sudo apt-get install -y"),
+                   "sudo apt-get install --allow-downgrades -y");
+        assert_eq!(downgrade_command("sudo yum install -y"), "sudo yum downgrade -y");
+        assert_eq!(downgrade_command("sudo zypper --non-interactive install"),
+                   "sudo zypper --non-interactive install --oldpackage");
+        assert_eq!(downgrade_command("sudo rpm -U"), "sudo rpm --oldpackage -U");
+        assert_eq!(downgrade_command("sudo dpkg -i"), "sudo dpkg -i");
+    }
+}
@@ -0,0 +1,329 @@
+use crate::error::{Error, Result};
+use crate::system::{find_in_path, SystemInfo};
+use crate::version::{dll_version_to_number, version_from_filename, version_string_to_number};
+use libloading::{Library, Symbol};
+use log::{debug, info};
+use regex::Regex;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+/// Where the Windows installer puts J-Link versions.
+const WINDOWS_INSTALL_GLOB: &str = "C:\\Program Files*\\SEGGER\\JLink*";
+
+/// Reads the version of the installed J-Link, in the numeric form used by
+/// `version_string_to_number`, from install directory names, the package database or
+/// J-Link Commander's banner.
+/// Loading the J-Link library to ask it runs the library's code in this process, so that's
+/// only tried as a last resort when `allow_dlopen` is set.
+pub fn get_current_installed_version(system_info: &SystemInfo, allow_dlopen: bool)
+    -> Option<i32> {
+    let version = match system_info.system.as_str() {
+        "Linux" => install_dir_version(system_info).or_else(package_db_version),
+        "MacOSX" => install_dir_version(system_info).or_else(pkgutil_version),
+        "Windows" => registry_version().or_else(|| install_dir_version(system_info)),
+        _ => return None,
+    }.or_else(|| commander_version(system_info));
+    if version.is_none() && allow_dlopen {
+        return dll_version(system_info);
+    }
+    version
+}
+
+/// The directories J-Link versions may be installed into, most likely first: on Linux the
+/// `prefix` tarballs are extracted into, /opt/SEGGER where the packages install, then
+/// /usr/local and user-local locations. Empty on Windows, where installs are found by glob.
+pub fn install_roots(system_info: &SystemInfo) -> Vec<PathBuf> {
+    let home = dirs::home_dir();
+    let mut roots = match system_info.system.as_str() {
+        "Linux" => {
+            let mut roots = vec![
+                system_info.prefix.clone(),
+                PathBuf::from("/opt/SEGGER"),
+                PathBuf::from("/usr/local/SEGGER"),
+            ];
+            roots.extend(home.map(|home| home.join(".local").join("SEGGER")));
+            roots
+        },
+        "MacOSX" => {
+            let mut roots = vec![PathBuf::from("/Applications/SEGGER")];
+            roots.extend(home.map(|home| home.join("Applications").join("SEGGER")));
+            roots
+        },
+        _ => Vec::new(),
+    };
+    let mut seen = Vec::new();
+    roots.retain(|root| {
+        let new = !seen.contains(root);
+        seen.push(root.clone());
+        new
+    });
+    roots
+}
+
+/// The newest J-Link install directory, judged by the version in its name.
+pub fn newest_install_dir(system_info: &SystemInfo) -> Option<PathBuf> {
+    install_roots(system_info).iter()
+        .filter_map(|root| glob::glob(&format!("{}/JLink*", root.display())).ok())
+        .flat_map(|paths| paths.flatten())
+        .filter(|path| path.is_dir())
+        .max_by_key(|path| {
+            path.file_name()
+                .and_then(|name| version_from_filename(&name.to_string_lossy()))
+                .unwrap_or(0)
+        })
+}
+
+/// Every J-Link install directory found, including Windows' under Program Files.
+fn install_dirs(system_info: &SystemInfo) -> Vec<PathBuf> {
+    let patterns = match system_info.system.as_str() {
+        "Windows" => vec![WINDOWS_INSTALL_GLOB.to_string()],
+        _ => install_roots(system_info).iter()
+            .map(|root| format!("{}/JLink*", root.display()))
+            .collect(),
+    };
+    patterns.iter()
+        .filter_map(|pattern| glob::glob(pattern).ok())
+        .flat_map(|paths| paths.flatten())
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+/// The version named by an install directory such as `JLink_V794`, if it names one.
+fn dir_version(dir: &Path) -> Option<i32> {
+    version_from_filename(&dir.file_name()?.to_string_lossy())
+}
+
+/// The newest version named by an install directory.
+fn install_dir_version(system_info: &SystemInfo) -> Option<i32> {
+    let newest = install_dirs(system_info).into_iter()
+        .filter_map(|dir| Some((dir_version(&dir)?, dir)))
+        .max_by_key(|(version, _)| *version);
+    let Some((version, dir)) = newest else {
+        debug!("No install directory is named with a version");
+        return None;
+    };
+    debug!("Using installed version from {}", dir.display());
+    Some(version)
+}
+
+/// Reads the version from the banner J-Link Commander prints on startup, e.g.
+/// "SEGGER J-Link Commander V7.94e (Compiled Feb 20 2024 15:54:34)".
+fn parse_commander_version(output: &str) -> Option<i32> {
+    let re = Regex::new(r"J-Link Commander\s+([vV]\d+\.\d+[a-z]*)").ok()?;
+    version_string_to_number(re.captures(output)?.get(1)?.as_str())
+}
+
+/// J-Link Commander from the newest install, or from PATH.
+fn find_commander(system_info: &SystemInfo) -> Option<PathBuf> {
+    let exe = if system_info.system == "Windows" { "JLink.exe" } else { "JLinkExe" };
+    let mut dirs = install_dirs(system_info);
+    dirs.sort_by_key(|dir| std::cmp::Reverse(dir_version(dir)));
+    dirs.iter()
+        .map(|dir| dir.join(exe))
+        .find(|path| path.is_file())
+        .or_else(|| find_in_path(exe))
+}
+
+/// What J-Link Commander did when started without a probe and told to exit.
+struct CommanderRun {
+    /// Exit status, or None if it didn't exit in time and was killed
+    status: Option<ExitStatus>,
+    stdout: String,
+    stderr: String,
+}
+
+/// Starts J-Link Commander without connecting to a target and tells it to exit straight
+/// away. It's killed if it hasn't exited after a few seconds.
+fn run_commander(commander: &Path) -> std::io::Result<CommanderRun> {
+    const TIMEOUT: Duration = Duration::from_secs(10);
+    let mut child = Command::new(commander)
+        .args(["-NoGui", "1", "-AutoConnect", "0"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"exit\n");
+    }
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if start.elapsed() > TIMEOUT {
+            debug!("{} did not exit; killing it", commander.display());
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stdout.take() {
+        pipe.read_to_string(&mut stdout)?;
+    }
+    if let Some(mut pipe) = child.stderr.take() {
+        pipe.read_to_string(&mut stderr)?;
+    }
+    Ok(CommanderRun { status, stdout, stderr })
+}
+
+/// Runs J-Link Commander and reads its version from the banner.
+fn commander_version(system_info: &SystemInfo) -> Option<i32> {
+    let commander = find_commander(system_info)?;
+    debug!("Reading version from {}", commander.display());
+    parse_commander_version(&run_commander(&commander).ok()?.stdout)
+}
+
+/// Checks that the installed J-Link Commander starts, loads the J-Link library and exits
+/// cleanly, which catches installs that report the right version but don't work.
+pub fn smoke_test(system_info: &SystemInfo) -> Result<()> {
+    let commander = find_commander(system_info).ok_or_else(|| {
+        Error::Install("smoke test failed: J-Link Commander not found".to_string())
+    })?;
+    debug!("Running {}", commander.display());
+    let run = run_commander(&commander)?;
+    let problem = match run.status {
+        None => "did not exit".to_string(),
+        Some(status) if !status.success() => format!("exited with {}", status),
+        Some(_) if parse_commander_version(&run.stdout).is_none() => {
+            "did not print its version".to_string()
+        },
+        Some(_) => {
+            info!("Smoke test passed: {} runs", commander.display());
+            return Ok(());
+        },
+    };
+    Err(Error::Install(format!("smoke test failed: {} {}\nstdout:\n{}\nstderr:\n{}",
+                               commander.display(), problem, run.stdout.trim_end(),
+                               run.stderr.trim_end())))
+}
+
+pub(crate) fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Asks dpkg or rpm, whichever is present, for the version of the `jlink` package.
+fn package_db_version() -> Option<i32> {
+    let version = if find_in_path("dpkg-query").is_some() {
+        command_output("dpkg-query", &["-W", "-f=${Version}", "jlink"])
+    } else if find_in_path("rpm").is_some() {
+        command_output("rpm", &["-q", "--qf", "%{VERSION}", "jlink"])
+    } else {
+        None
+    }?;
+    version_string_to_number(version.trim())
+}
+
+/// Asks the macOS installer database for the version of SEGGER's J-Link package.
+fn pkgutil_version() -> Option<i32> {
+    let pkgs = command_output("pkgutil", &["--pkgs"])?;
+    let pkg_id = pkgs.lines()
+        .map(str::trim)
+        .find(|id| {
+            let id = id.to_lowercase();
+            id.contains("segger") && id.contains("jlink")
+        })?;
+
+    let info = command_output("pkgutil", &["--pkg-info", pkg_id])?;
+    let version = info.lines()
+        .find_map(|line| line.strip_prefix("version:"))?;
+    version_string_to_number(version.trim())
+}
+
+/// Reads the version from SEGGER J-Link's entry under the registry's uninstall keys.
+#[cfg(windows)]
+fn registry_version() -> Option<i32> {
+    use winreg::enums::HKEY_LOCAL_MACHINE;
+    use winreg::RegKey;
+
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    [
+        "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+        "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+    ].iter()
+        .filter_map(|path| hklm.open_subkey(path).ok())
+        .flat_map(|uninstall| {
+            uninstall.enum_keys().flatten()
+                .filter_map(|name| uninstall.open_subkey(name).ok())
+                .collect::<Vec<_>>()
+        })
+        .filter_map(|key| {
+            let name: String = key.get_value("DisplayName").ok()?;
+            if !name.contains("J-Link") {
+                return None;
+            }
+            let version: String = key.get_value("DisplayVersion").unwrap_or(name);
+            version_string_to_number(&version)
+        })
+        .max()
+}
+
+#[cfg(not(windows))]
+fn registry_version() -> Option<i32> {
+    None
+}
+
+fn dll_version(system_info: &SystemInfo) -> Option<i32> {
+    let dll_paths = match system_info.system.as_str() {
+        "Windows" => vec![format!("{}\\JLink*.dll", WINDOWS_INSTALL_GLOB)],
+        _ => install_roots(system_info).iter()
+            .map(|root| format!("{}/JLink*/libjlink*", root.display()))
+            .collect(),
+    };
+
+    // Several versions can be installed side by side, so the newest of them all counts
+    let newest = dll_paths.iter()
+        .filter_map(|pattern| glob::glob(pattern).ok())
+        .flat_map(|paths| paths.flatten())
+        .filter_map(|path| Some((read_dll_version(&path)?, path)))
+        .max_by_key(|(version, _)| *version);
+    let Some((version, path)) = newest else {
+        debug!("No J-Link library matching {} reported a version", dll_paths.join(" or "));
+        return None;
+    };
+    debug!("Using installed version from {}", path.display());
+    Some(version)
+}
+
+/// Loads the J-Link library at `path` and asks it for its version.
+fn read_dll_version(path: &Path) -> Option<i32> {
+    debug!("Reading version from {}", path.display());
+    // A failure here only rules out this library; the caller goes on to the next candidate
+    let lib = unsafe { Library::new(path) }
+        .map_err(|e| debug!("Could not load {}: {}", path.display(), e))
+        .ok()?;
+    // Old libraries only export the function under its original JLINKARM_ name
+    let func: Symbol<unsafe extern "C" fn() -> i32> = unsafe { lib.get(b"JLINK_GetDLLVersion") }
+        .or_else(|_| unsafe { lib.get(b"JLINKARM_GetDLLVersion") })
+        .map_err(|e| debug!("{} has no JLINK_GetDLLVersion, so it's not a J-Link library or \
+                             its symbols were stripped: {}", path.display(), e))
+        .ok()?;
+    let dll_version = unsafe { func() };
+    let version = dll_version_to_number(dll_version);
+    if version.is_none() {
+        debug!("{} reported unrecognized version {}", path.display(), dll_version);
+    }
+    version
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commander_banner_version() {
+        let banner = "SEGGER J-Link Commander V7.94e (Compiled Feb 20 2024 15:54:34)\n\
+                      DLL version V7.94e, compiled Feb 20 2024 15:53:59\n";
+        assert_eq!(parse_commander_version(banner), Some(79405));
+        assert_eq!(parse_commander_version("J-Link>"), None);
+    }
+}
@@ -0,0 +1,63 @@
+//! Checks for, downloads and installs the latest SEGGER J-Link software package.
+
+pub mod backup;
+pub mod cache;
+pub mod config;
+pub mod download;
+pub mod error;
+pub mod install;
+pub mod installed;
+pub mod lock;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod notify;
+pub mod postinstall;
+pub mod processes;
+pub mod product;
+pub mod report;
+pub mod self_update;
+pub mod source;
+pub mod system;
+#[cfg(test)]
+mod test_util;
+pub mod version;
+
+pub use backup::{backup_install, latest_backup, restore_backup};
+pub use cache::{
+    clear_cache, load_cached_package, load_cached_versions, store_cached_package,
+    store_cached_versions,
+};
+pub use config::{default_config_path, load_config, Config};
+pub use download::{
+    download, parse_byte_size, parse_versions, redirect_policy, sha256_file, verify_package,
+    DownloadOptions, HttpClient,
+};
+pub use error::{Error, Result};
+pub use install::{check_install_command, install, install_command, InstallOptions};
+pub use installed::{get_current_installed_version, newest_install_dir, smoke_test};
+pub use lock::acquire_lock;
+#[cfg(feature = "async")]
+pub use nonblocking::AsyncHttpClient;
+pub use notify::notify_update;
+pub use postinstall::{install_udev_rules, link_executables};
+pub use processes::running_segger_processes;
+pub use product::{find_product, Product, JLINK, PRODUCTS};
+pub use report::{version_entries, versions_to_json, Report, VersionEntry};
+pub use self_update::{is_newer_version, latest_release, self_update, Release};
+pub use source::{Downloader, Fetch, Mirrors, VersionList, VersionSource};
+pub use system::{
+    architectures, get_system_info, SystemInfo, SystemOptions, DEFAULT_FILENAME_TEMPLATE,
+};
+pub use version::{
+    count_newer_versions, dll_version_to_number, is_prerelease, select_latest_version,
+    select_target_version, version_from_filename, version_number_to_string,
+    version_string_to_number,
+};
+
+/// The SEGGER J-Link download page, which lists available versions and serves packages.
+pub const JLINK_URL: &str = "https://www.segger.com/downloads/jlink/";
+
+/// The User-Agent sent with every request unless overridden.
+pub const USER_AGENT: &str = concat!(
+    "JLinkUpdate/", env!("CARGO_PKG_VERSION"), " (+https://github.com/FletcherD/JLinkUpdate)"
+);
@@ -0,0 +1,54 @@
+use crate::error::{Error, Result};
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// The lock file that keeps concurrent runs from downloading and installing at once.
+pub fn lock_path() -> PathBuf {
+    std::env::temp_dir().join("jlinkupdate.lock")
+}
+
+/// Takes an exclusive lock on `lock_path()`, held until the returned file is dropped or the
+/// process exits.
+pub fn acquire_lock() -> Result<File> {
+    lock(&lock_path())
+}
+
+fn lock(path: &Path) -> Result<File> {
+    // The lock file may belong to another user, e.g. after a sudo run. Locking doesn't need
+    // write access, so opening it read-only is enough.
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+        .or_else(|e| match e.kind() {
+            ErrorKind::PermissionDenied => File::open(path),
+            _ => Err(e),
+        })
+        .map_err(|e| Error::LockFile(path.to_path_buf(), e))?;
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(std::fs::TryLockError::WouldBlock) => Err(Error::Locked(path.to_path_buf())),
+        Err(std::fs::TryLockError::Error(e)) => Err(Error::LockFile(path.to_path_buf(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn locks_read_only_lock_file() {
+        let dir = TempDir::new("lock");
+        let path = dir.path().join("jlinkupdate.lock");
+        File::create(&path).unwrap();
+        let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions).unwrap();
+
+        let _lock = lock(&path).unwrap();
+        assert!(matches!(lock(&path), Err(Error::Locked(_))));
+    }
+}
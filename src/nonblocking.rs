@@ -0,0 +1,136 @@
+//! Async counterparts of `HttpClient` and `download`, built on reqwest's async client, for
+//! applications that already run an async runtime. Enabled with the `async` feature.
+use crate::download::{
+    check_final_url, download_status_error, html_download_error, is_html, judge_attempt,
+    parse_versions, partial_path, status_error, verify_download, Attempt, DownloadOptions,
+    LICENSE_FIELD,
+};
+use crate::error::{Error, Result};
+use crate::product::Product;
+use log::debug;
+use reqwest::{Client, RequestBuilder, Response};
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+
+/// Scrapes versions from and downloads packages off SEGGER's site without blocking.
+#[derive(Debug, Clone)]
+pub struct AsyncHttpClient {
+    client: Client,
+    page_url: String,
+    retries: u32,
+}
+
+impl AsyncHttpClient {
+    /// Uses `client` with the download page at `page_url`, retrying failed requests `retries`
+    /// times.
+    pub fn new(client: Client, page_url: &str, retries: u32) -> Self {
+        AsyncHttpClient {
+            client,
+            page_url: page_url.to_string(),
+            retries,
+        }
+    }
+
+    /// Sends a request, retrying connection errors and 5xx responses with exponential
+    /// backoff, like `send_with_retry`.
+    async fn send_with_retry(&self, request: impl Fn() -> RequestBuilder) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            let sent = request().send().await;
+            match judge_attempt(sent, Response::status, attempt, self.retries) {
+                Attempt::Done(result) => return result,
+                Attempt::Retry(delay) => tokio::time::sleep(delay).await,
+            }
+            attempt += 1;
+        }
+    }
+
+    /// The versions of `product` offered for download, in page order.
+    pub async fn versions(&self, product: &Product) -> Result<Vec<String>> {
+        let response = self.send_with_retry(|| self.client.get(&self.page_url)).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(status_error(status, &response.text().await.unwrap_or_default()));
+        }
+        parse_versions(&response.text().await?, product)
+    }
+
+    /// Downloads `file_url` to `download_path` via a `.part` file and verifies it like
+    /// `download`. Unlike `download` it doesn't resume partial files, limit the rate or show
+    /// progress.
+    pub async fn download(&self, file_url: &str, download_path: &Path,
+                          options: &DownloadOptions) -> Result<()> {
+        debug!("Requesting {}", file_url);
+        let mut response = self.send_with_retry(|| {
+            self.client.post(file_url).form(&[(LICENSE_FIELD, "accepted")])
+        }).await?;
+
+        check_final_url(file_url, response.url().as_str(), &self.page_url)?;
+        let status = response.status();
+        if status != 200 {
+            let body = response.text().await.unwrap_or_default();
+            return Err(download_status_error(file_url, status, &body));
+        }
+        if is_html(response.headers()) {
+            return Err(html_download_error(file_url, &response.text().await?));
+        }
+
+        if let Some(parent) = download_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let content_length = response.content_length();
+        let part_path = partial_path(download_path);
+        let mut file = tokio::fs::File::create(&part_path).await?;
+        let mut written = 0;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        file.flush().await?;
+
+        // Hashing reads the whole package, so it runs off the async threads
+        let verify_path = part_path.clone();
+        let verify_options = options.clone();
+        let verified = tokio::task::spawn_blocking(move || {
+            verify_download(&verify_path, &verify_options, written, content_length)
+        }).await.map_err(|e| Error::Download(format!("verification did not finish: {}", e)))?;
+        if let Err(e) = verified {
+            tokio::fs::remove_file(&part_path).await?;
+            return Err(e);
+        }
+        tokio::fs::rename(&part_path, download_path).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::JLINK;
+    use crate::test_util::TempDir;
+
+    #[tokio::test]
+    async fn downloads_without_blocking() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/")
+            .with_body("<select class=\"version\"><option>V7.94e</option></select>")
+            .create_async()
+            .await;
+        server.mock("POST", "/JLink_Linux_V794e_x86_64.deb")
+            .match_body("accept_license_agreement=accepted")
+            .with_body("fake installer")
+            .create_async()
+            .await;
+
+        let client = AsyncHttpClient::new(Client::new(), &format!("{}/", server.url()), 0);
+        assert_eq!(client.versions(&JLINK).await.unwrap(), ["V7.94e"]);
+
+        let dir = TempDir::new("async");
+        let path = dir.path().join("JLink_Linux_V794e_x86_64.deb");
+        let url = format!("{}/JLink_Linux_V794e_x86_64.deb", server.url());
+        let result = client.download(&url, &path, &DownloadOptions::default()).await;
+
+        result.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fake installer");
+    }
+}
@@ -0,0 +1,21 @@
+use crate::error::{Error, Result};
+use crate::product::Product;
+use notify_rust::Notification;
+
+/// Shows a desktop notification that `latest` of `product` is available, mentioning the
+/// `installed` version if there is one.
+pub fn notify_update(product: &Product, installed: Option<&str>, latest: &str) -> Result<()> {
+    let body = match installed {
+        Some(installed) => {
+            format!("{} {} is available; {} is installed.", product.title, latest, installed)
+        },
+        None => format!("{} {} is available.", product.title, latest),
+    };
+    Notification::new()
+        .summary(&format!("{} update available", product.title))
+        .body(&body)
+        .appname("JLinkUpdate")
+        .show()
+        .map_err(|e| Error::Notification(e.to_string()))?;
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use crate::error::{Error, Result};
+use crate::installed::newest_install_dir;
+use crate::system::{check_sudo, SystemInfo};
+use log::{debug, info, warn};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const UDEV_RULES_FILE: &str = "99-jlink.rules";
+const UDEV_RULES_DIR: &str = "/etc/udev/rules.d";
+
+/// The executables linked onto PATH by `link_executables`.
+pub const LINKED_EXECUTABLES: &[&str] = &[
+    "JLinkExe", "JLinkGDBServer", "JLinkGDBServerCLExe", "JLinkRTTClient",
+];
+
+/// Runs `program` with `args`, through sudo if the install command uses it.
+pub(crate) fn run_privileged(system_info: &SystemInfo, program: &str, args: &[&str]) -> Result<()> {
+    let use_sudo = system_info.package_install_cmd.split_whitespace().next() == Some("sudo");
+    if use_sudo {
+        check_sudo(&["sudo".to_string(), program.to_string()])?;
+    }
+    let mut command = if use_sudo {
+        let mut command = Command::new("sudo");
+        command.arg(program);
+        command
+    } else {
+        Command::new(program)
+    };
+    command.args(args);
+    debug!("Running {:?}", command);
+
+    let status = command.status()?;
+    if !status.success() {
+        return Err(Error::Install(format!("{} exited with {}", program, status)));
+    }
+    Ok(())
+}
+
+/// Copies SEGGER's udev rules from the newest J-Link install into `/etc/udev/rules.d` so
+/// non-root users can access probes, then reloads udev. Returns the installed rules file.
+pub fn install_udev_rules(system_info: &SystemInfo) -> Result<PathBuf> {
+    if system_info.system != "Linux" {
+        return Err(Error::InvalidOptions("udev rules are only used on Linux".to_string()));
+    }
+    let rules = newest_install_dir(system_info)
+        .map(|dir| dir.join(UDEV_RULES_FILE))
+        .filter(|rules| rules.is_file())
+        .ok_or_else(|| Error::Install(format!("could not find {} in a J-Link install",
+                                              UDEV_RULES_FILE)))?;
+
+    let rules_str = rules.to_string_lossy();
+    run_privileged(system_info, "cp", &[&rules_str, UDEV_RULES_DIR])?;
+    run_privileged(system_info, "udevadm", &["control", "--reload"])?;
+
+    let installed = PathBuf::from(UDEV_RULES_DIR).join(rules.file_name().unwrap_or_default());
+    info!("Installed udev rules {} from {}", installed.display(), rules.display());
+    Ok(installed)
+}
+
+/// Symlinks the newest install's key executables into `bin_dir`, replacing symlinks left by
+/// a previous version. Regular files in the way are left alone. Returns the links created.
+pub fn link_executables(system_info: &SystemInfo, bin_dir: &Path) -> Result<Vec<PathBuf>> {
+    if system_info.system != "Linux" {
+        return Err(Error::InvalidOptions("executables are only linked on Linux".to_string()));
+    }
+    let install_dir = newest_install_dir(system_info)
+        .ok_or_else(|| Error::Install("could not find a J-Link install".to_string()))?;
+
+    let mut links = Vec::new();
+    for name in LINKED_EXECUTABLES {
+        let target = install_dir.join(name);
+        if !target.is_file() {
+            debug!("{} not found, not linking it", target.display());
+            continue;
+        }
+        let link = bin_dir.join(name);
+        let is_symlink = std::fs::symlink_metadata(&link)
+            .map(|m| m.file_type().is_symlink());
+        if let Ok(false) = is_symlink {
+            warn!("{} exists and is not a symlink; leaving it alone", link.display());
+            continue;
+        }
+        run_privileged(system_info, "ln", &["-sfn", &target.to_string_lossy(),
+                                            &link.to_string_lossy()])?;
+        info!("Linked {} -> {}", link.display(), target.display());
+        links.push(link);
+    }
+    Ok(links)
+}
@@ -0,0 +1,41 @@
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System};
+
+/// Whether a process called `name` is a SEGGER tool that may hold files the installer needs to
+/// replace, like JLinkGDBServer, JLink.exe, J-Flash or Ozone.
+fn is_segger_process(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let name = name.strip_suffix(".exe").unwrap_or(&name);
+    // JLinkUpdate is this tool, not something for the installer to worry about
+    (name.starts_with("jlink") && name != "jlinkupdate")
+        || name.starts_with("jflash")
+        || name == "ozone"
+}
+
+/// Running SEGGER tools, as process id and name, sorted by id.
+pub fn running_segger_processes() -> Vec<(u32, String)> {
+    let mut system = System::new_with_specifics(
+        RefreshKind::nothing().with_processes(ProcessRefreshKind::nothing()));
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    let mut processes: Vec<(u32, String)> = system.processes().iter()
+        .map(|(pid, process)| (pid.as_u32(), process.name().to_string_lossy().into_owned()))
+        .filter(|(_, name)| is_segger_process(name))
+        .collect();
+    processes.sort();
+    processes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segger_process_names() {
+        for name in ["JLinkGDBServerCLExe", "JLinkExe", "JLink.exe", "JLinkRemoteServer",
+                     "JFlashLite", "Ozone", "Ozone.exe"] {
+            assert!(is_segger_process(name), "{}", name);
+        }
+        for name in ["JLinkUpdate", "JLinkUpdate.exe", "bash", "ozoned"] {
+            assert!(!is_segger_process(name), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,39 @@
+/// A SEGGER product offered on the J-Link download page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Product {
+    /// Name used to select the product with `--package`
+    pub name: &'static str,
+    /// Name shown to the user, e.g. "J-Link"
+    pub title: &'static str,
+    /// Prefix of the product's package names, e.g. "JLink" in `JLink_Linux_V794_x86_64.deb`
+    pub file_prefix: &'static str,
+    /// Id of the download page section holding the product's version dropdown, or None for
+    /// the first dropdown on the page
+    pub section: Option<&'static str>,
+}
+
+/// The J-Link Software and Documentation Pack, which also contains J-Flash and the manuals.
+pub const JLINK: Product = Product {
+    name: "jlink",
+    title: "J-Link",
+    file_prefix: "JLink",
+    section: None,
+};
+
+/// The Ozone debugger.
+pub const OZONE: Product = Product {
+    name: "ozone",
+    title: "Ozone",
+    file_prefix: "Ozone",
+    section: Some("Ozone"),
+};
+
+/// Every product `--package` can select. There's no separate documentation product: the
+/// manuals ship in the J-Link pack, and SEGGER doesn't publish them as versioned packages per
+/// platform.
+pub const PRODUCTS: &[Product] = &[JLINK, OZONE];
+
+/// The product called `name`, if there is one.
+pub fn find_product(name: &str) -> Option<Product> {
+    PRODUCTS.iter().copied().find(|product| product.name.eq_ignore_ascii_case(name))
+}
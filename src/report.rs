@@ -0,0 +1,107 @@
+use crate::product::Product;
+use crate::system::SystemInfo;
+use crate::version::{is_prerelease, version_string_to_number};
+use serde::Serialize;
+
+/// The machine-readable result of a run, printed as a single JSON object by `--format json`.
+/// Fields are only ever added to this schema, never renamed or removed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    /// Product the run was for, e.g. "jlink"
+    pub package: String,
+    /// Architecture token used in the package name, e.g. "x86_64"
+    pub arch: String,
+    /// System token used in the package name: "Linux", "MacOSX" or "Windows"
+    pub system: String,
+    /// Package type, e.g. "deb"
+    pub package_type: String,
+    /// Version selected for install, as shown on the SEGGER download page, e.g. "V7.88a"
+    pub latest_version: Option<String>,
+    /// `latest_version` in numeric form, e.g. 78801
+    pub latest_version_number: Option<i32>,
+    /// Version installed before this run, e.g. "V7.80"
+    pub installed_version: Option<String>,
+    /// `installed_version` in numeric form
+    pub installed_version_number: Option<i32>,
+    /// Whether `latest_version` is newer than `installed_version`
+    pub update_available: bool,
+    /// Number of releases between `installed_version` and `latest_version`, counting the
+    /// latest, when the installed version is known
+    pub newer_versions: Option<usize>,
+    /// Whether this run installed a new version
+    pub updated: bool,
+    /// URL the package was (or, with `--dry-run`, would be) downloaded from
+    pub download_url: Option<String>,
+    /// Local path the package was (or, with `--dry-run`, would be) downloaded to
+    pub download_path: Option<String>,
+    /// Directory the new version was installed into, where that can be found
+    pub install_dir: Option<String>,
+}
+
+/// One release in the `--json-versions` list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VersionEntry {
+    /// Version as shown on the SEGGER download page, e.g. "V7.88a"
+    pub display: String,
+    /// `display` in numeric form, e.g. 78801
+    pub number: i32,
+    /// Whether this is a beta or release candidate
+    pub prerelease: bool,
+}
+
+/// The parseable versions in `versions`, newest first.
+pub fn version_entries(versions: &[String]) -> Vec<VersionEntry> {
+    let mut entries: Vec<VersionEntry> = versions.iter()
+        .filter_map(|version| Some(VersionEntry {
+            display: version.clone(),
+            number: version_string_to_number(version)?,
+            prerelease: is_prerelease(version),
+        }))
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.number));
+    entries
+}
+
+/// `versions` as the JSON array printed by `--json-versions`.
+pub fn versions_to_json(versions: &[String]) -> String {
+    serde_json::to_string(&version_entries(versions)).expect("versions are always serializable")
+}
+
+impl Report {
+    pub fn new(system_info: &SystemInfo, product: &Product) -> Self {
+        Report {
+            package: product.name.to_string(),
+            arch: system_info.arch.clone(),
+            system: system_info.system.clone(),
+            package_type: system_info.package_type.clone(),
+            latest_version: None,
+            latest_version_number: None,
+            installed_version: None,
+            installed_version_number: None,
+            update_available: false,
+            newer_versions: None,
+            updated: false,
+            download_url: None,
+            download_path: None,
+            install_dir: None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("report is always serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_versions_as_json() {
+        let versions = ["V7.80", "V7.88a", "not a version", "V7.90 (beta)"].map(String::from);
+        assert_eq!(versions_to_json(&versions),
+                   r#"[{"display":"V7.90 (beta)","number":79000,"prerelease":true},"#.to_owned()
+                   + r#"{"display":"V7.88a","number":78801,"prerelease":false},"#
+                   + r#"{"display":"V7.80","number":78000,"prerelease":false}]"#);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::download::{response_error, send_with_retry, sha256_file};
+use crate::error::{Error, Result};
+use log::{debug, info};
+use reqwest::blocking::Client;
+use reqwest::header::ACCEPT;
+use serde::Deserialize;
+use std::fs::File;
+
+/// GitHub API endpoint for JLinkUpdate's newest release.
+pub const RELEASES_URL: &str = "https://api.github.com/repos/FletcherD/JLinkUpdate/releases/latest";
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// The binary published for this platform in a JLinkUpdate release.
+#[derive(Debug, Clone)]
+pub struct Release {
+    /// Release version without the leading 'v', e.g. "0.2.0"
+    pub version: String,
+    /// File name of the binary asset
+    pub asset_name: String,
+    /// Where the binary is downloaded from
+    pub asset_url: String,
+    /// Where the `<asset>.sha256` checksum of the binary is downloaded from
+    pub sha256_url: String,
+}
+
+/// Names the OS might go by in a release asset name.
+fn os_names() -> &'static [&'static str] {
+    match std::env::consts::OS {
+        "linux" => &["linux"],
+        "macos" => &["macos", "darwin", "apple"],
+        "windows" => &["windows"],
+        _ => &[],
+    }
+}
+
+/// Endings of release assets that aren't the bare binary: checksums, signatures, archives and
+/// packages. Installing one of these as the executable would break it.
+const NON_BINARY_SUFFIXES: &[&str] = &[
+    ".sha256", ".sig", ".asc", ".tar.gz", ".tgz", ".tar.xz", ".txz", ".tar.bz2", ".tbz2",
+    ".tar.zst", ".tar", ".gz", ".xz", ".bz2", ".zst", ".zip", ".7z", ".deb", ".rpm", ".msi",
+    ".dmg", ".pkg",
+];
+
+/// Whether release asset `name` is a binary for the running OS and architecture.
+fn asset_matches_platform(name: &str) -> bool {
+    let name = name.to_lowercase();
+    !NON_BINARY_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+        && name.contains(std::env::consts::ARCH)
+        && os_names().iter().any(|os| name.contains(os))
+}
+
+/// Splits a version like "1.2.10" into its numeric parts for comparison.
+fn version_parts(version: &str) -> Vec<u64> {
+    version.trim_start_matches(['v', 'V'])
+        .split('.')
+        .map(|part| part.chars().take_while(char::is_ascii_digit).collect::<String>())
+        .map(|digits| digits.parse().unwrap_or(0))
+        .collect()
+}
+
+/// Whether release `version` is newer than `current`.
+pub fn is_newer_version(version: &str, current: &str) -> bool {
+    version_parts(version) > version_parts(current)
+}
+
+/// Looks up the newest JLinkUpdate release and its binary for the running platform.
+pub fn latest_release(client: &Client, retries: u32) -> Result<Release> {
+    let response = send_with_retry(retries, || {
+        client.get(RELEASES_URL).header(ACCEPT, "application/vnd.github+json")
+    })?;
+    if !response.status().is_success() {
+        return Err(response_error(response));
+    }
+    let release: GithubRelease = response.json()?;
+
+    let asset = release.assets.iter()
+        .find(|asset| asset_matches_platform(&asset.name))
+        .ok_or_else(|| Error::Download(format!("release {} has no binary for {}-{}",
+                                               release.tag_name, std::env::consts::OS,
+                                               std::env::consts::ARCH)))?;
+    let sha256_name = format!("{}.sha256", asset.name);
+    let sha256_asset = release.assets.iter()
+        .find(|other| other.name == sha256_name)
+        .ok_or_else(|| Error::Download(format!("release {} has no {} to verify against",
+                                               release.tag_name, sha256_name)))?;
+
+    Ok(Release {
+        version: release.tag_name.trim_start_matches(['v', 'V']).to_string(),
+        asset_name: asset.name.clone(),
+        asset_url: asset.browser_download_url.clone(),
+        sha256_url: sha256_asset.browser_download_url.clone(),
+    })
+}
+
+/// Downloads `release`'s binary, checks it against the published SHA256 and replaces the
+/// running executable with it.
+pub fn self_update(client: &Client, release: &Release, retries: u32) -> Result<()> {
+    let response = send_with_retry(retries, || client.get(&release.sha256_url))?;
+    if !response.status().is_success() {
+        return Err(response_error(response));
+    }
+    let checksum = response.text()?;
+    let expected = checksum.split_whitespace().next()
+        .ok_or_else(|| Error::Download(format!("empty checksum file for {}", release.asset_name)))?
+        .to_string();
+
+    let path = std::env::temp_dir().join(format!("jlinkupdate-{}", release.asset_name));
+    debug!("Downloading {} to {}", release.asset_url, path.display());
+    let mut response = send_with_retry(retries, || client.get(&release.asset_url))?;
+    if !response.status().is_success() {
+        return Err(response_error(response));
+    }
+    std::io::copy(&mut response, &mut File::create(&path)?)?;
+
+    let actual = sha256_file(&path)?;
+    if !actual.eq_ignore_ascii_case(&expected) {
+        std::fs::remove_file(&path)?;
+        return Err(Error::Download(format!("SHA256 mismatch for {}: expected {}, got {}",
+                                           release.asset_name, expected, actual)));
+    }
+    info!("SHA256 verified: {}", actual);
+
+    let replaced = self_replace::self_replace(&path);
+    std::fs::remove_file(&path)?;
+    replaced?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_release_versions() {
+        assert!(is_newer_version("v0.2.0", "0.1.9"));
+        assert!(is_newer_version("0.10.0", "0.9.1"));
+        assert!(!is_newer_version("v0.1.0", "0.1.0"));
+        assert!(!is_newer_version("0.1.0", "0.2.0"));
+    }
+
+    #[test]
+    fn picks_bare_binary_over_archives() {
+        let platform = format!("jlinkupdate-{}-{}", os_names()[0], std::env::consts::ARCH);
+        let binary = format!("{}{}", platform, std::env::consts::EXE_SUFFIX);
+        let mut names: Vec<String> = [".tar.gz", ".zip", ".tgz", ".sha256"].iter()
+            .map(|suffix| format!("{}{}", platform, suffix))
+            .collect();
+        names.push("jlinkupdate-other-os-riscv".to_string());
+        names.push(binary.clone());
+        names.push(format!("{}.sha256", binary));
+
+        let found: Vec<&String> = names.iter()
+            .filter(|name| asset_matches_platform(name))
+            .collect();
+        assert_eq!(found, [&binary]);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::error::{Error, Result};
+use crate::product::Product;
+use crate::version::{
+    count_newer_versions, is_prerelease, select_latest_version, select_target_version,
+};
+use log::{debug, warn};
+use std::io::Read;
+
+/// Where the list of available versions comes from.
+pub trait VersionSource {
+    /// The versions of `product` offered for download, in page order.
+    fn versions(&self, product: &Product) -> Result<Vec<String>>;
+}
+
+/// Version sources tried in turn, e.g. SEGGER's page followed by its mirrors.
+pub struct Mirrors<S> {
+    /// Each source with the URL or name it's reported by
+    sources: Vec<(String, S)>,
+}
+
+impl<S> Mirrors<S> {
+    pub fn new(sources: Vec<(String, S)>) -> Self {
+        Mirrors { sources }
+    }
+}
+
+impl<S: VersionSource> VersionSource for Mirrors<S> {
+    /// The version list from the first source that can provide it, or the last error.
+    fn versions(&self, product: &Product) -> Result<Vec<String>> {
+        let mut last_error = None;
+        for (name, source) in &self.sources {
+            match source.versions(product) {
+                Ok(versions) => return Ok(versions),
+                Err(e) => {
+                    warn!("Could not get the version list from {}: {}", name, e);
+                    last_error = Some(e);
+                },
+            }
+        }
+        Err(last_error.unwrap_or_else(|| Error::InvalidOptions("no download source".to_string())))
+    }
+}
+
+/// The versions on offer for a product, and those the latest is picked from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionList {
+    /// Every version offered, in page order
+    pub available: Vec<String>,
+    /// The versions the latest is picked from: pre-releases only count with `include_beta`
+    pub candidates: Vec<String>,
+}
+
+impl VersionList {
+    pub fn new(available: Vec<String>, include_beta: bool) -> Self {
+        let candidates = if include_beta {
+            available.clone()
+        } else {
+            let (beta, stable): (Vec<String>, Vec<String>) = available.iter()
+                .cloned()
+                .partition(|version| is_prerelease(version));
+            if !beta.is_empty() {
+                debug!("Skipping pre-release versions {}; pass --include-beta to consider them",
+                       beta.join(", "));
+            }
+            stable
+        };
+        VersionList { available, candidates }
+    }
+
+    /// Asks `source` for the versions of `product`.
+    pub fn fetch(source: &impl VersionSource, product: &Product, include_beta: bool)
+        -> Result<Self> {
+        Ok(VersionList::new(source.versions(product)?, include_beta))
+    }
+
+    /// The version to install and its number: `target_version` if given, which may be a
+    /// pre-release, otherwise the newest candidate.
+    pub fn select(&self, target_version: Option<&str>) -> Result<(String, i32)> {
+        match target_version {
+            Some(target_version) => select_target_version(&self.available, target_version),
+            None => select_latest_version(&self.candidates),
+        }
+    }
+
+    /// How many candidates are newer than `installed` but not newer than `latest`.
+    pub fn count_newer(&self, installed: i32, latest: i32) -> usize {
+        count_newer_versions(&self.candidates, installed, latest)
+    }
+}
+
+/// A package being fetched by a `Downloader`.
+pub struct Fetch {
+    /// Offset the body starts at; 0 unless the server agreed to resume a partial download
+    pub resume_from: u64,
+    /// Number of bytes in the body, if known
+    pub content_length: Option<u64>,
+    /// The package bytes from `resume_from` on
+    pub body: Box<dyn Read>,
+}
+
+/// Fetches package files.
+pub trait Downloader {
+    /// Starts fetching `url`, from byte `resume_from` on if the server supports it.
+    fn fetch(&self, url: &str, resume_from: u64) -> Result<Fetch>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::{JLINK, OZONE};
+    use std::collections::HashMap;
+
+    /// Versions held in memory, by product name; a missing product fails like a dead server.
+    struct FakeSource(HashMap<&'static str, Vec<&'static str>>);
+
+    impl VersionSource for FakeSource {
+        fn versions(&self, product: &Product) -> Result<Vec<String>> {
+            let versions = self.0.get(product.name)
+                .ok_or_else(|| Error::PageLayout(format!("no {} versions", product.name)))?;
+            Ok(versions.iter().map(|v| v.to_string()).collect())
+        }
+    }
+
+    fn source(versions: &[&'static str]) -> FakeSource {
+        FakeSource(HashMap::from([(JLINK.name, versions.to_vec())]))
+    }
+
+    #[test]
+    fn selects_from_fetched_versions() {
+        let source = source(&["V7.96 RC1", "V7.94e", "V7.94d", "V7.88a", "junk"]);
+        let stable = VersionList::fetch(&source, &JLINK, false).unwrap();
+        assert_eq!(stable.candidates, ["V7.94e", "V7.94d", "V7.88a", "junk"]);
+        assert_eq!(stable.select(None).unwrap(), ("V7.94e".to_string(), 79405));
+        assert_eq!(stable.select(Some("7.88a")).unwrap(), ("V7.88a".to_string(), 78801));
+        assert!(matches!(stable.select(Some("V7.90")), Err(Error::VersionNotFound { .. })));
+        assert_eq!(stable.count_newer(78801, 79405), 2);
+
+        let beta = VersionList::fetch(&source, &JLINK, true).unwrap();
+        assert_eq!(beta.select(None).unwrap().1, 79600);
+        assert!(VersionList::fetch(&source, &OZONE, false).is_err());
+    }
+
+    #[test]
+    fn falls_back_to_next_mirror() {
+        let mirrors = Mirrors::new(vec![
+            ("down".to_string(), FakeSource(HashMap::new())),
+            ("up".to_string(), source(&["V7.94e"])),
+        ]);
+        assert_eq!(mirrors.versions(&JLINK).unwrap(), ["V7.94e"]);
+        assert!(Mirrors::new(vec![("down".to_string(), source(&[]))]).versions(&OZONE).is_err());
+    }
+}
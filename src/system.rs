@@ -0,0 +1,466 @@
+use crate::error::{Error, Result};
+use crate::product::Product;
+use crate::version::filename_version;
+use std::path::{Path, PathBuf};
+
+/// SEGGER's package naming, e.g. `JLink_Linux_V794_x86_64.deb`. See
+/// `SystemInfo::package_filename_for_arch` for the placeholders.
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{product}_{system}_{version}_{arch}.{ext}";
+
+/// Placeholders a package name template can use.
+const FILENAME_PLACEHOLDERS: &[&str] = &["product", "system", "version", "arch", "ext"];
+
+/// The requested target platform; each field is either a specific value or "auto".
+#[derive(Debug, Clone)]
+pub struct SystemOptions {
+    pub arch: String,
+    pub system: String,
+    pub package_type: String,
+    pub package_install_cmd: String,
+    /// Directory Linux tarballs are extracted into
+    pub prefix: PathBuf,
+    /// Prefer package managers that resolve dependencies over plain dpkg and rpm
+    pub resolve_deps: bool,
+    /// How package names are built; see `DEFAULT_FILENAME_TEMPLATE`
+    pub filename_template: String,
+    /// Volume macOS packages are installed onto
+    pub macos_target: PathBuf,
+}
+
+impl Default for SystemOptions {
+    fn default() -> Self {
+        SystemOptions {
+            arch: "auto".to_string(),
+            system: "auto".to_string(),
+            package_type: "auto".to_string(),
+            package_install_cmd: "auto".to_string(),
+            prefix: PathBuf::from("/opt/SEGGER"),
+            resolve_deps: true,
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+            macos_target: PathBuf::from("/"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SystemInfo {
+    pub arch: String,
+    pub system: String,
+    pub package_type: String,
+    pub package_install_cmd: String,
+    /// Architectures to fall back to, in order, if no package exists for `arch`
+    pub fallback_archs: Vec<String>,
+    /// Directory Linux tarballs are extracted into
+    pub prefix: PathBuf,
+    /// How package names are built; see `DEFAULT_FILENAME_TEMPLATE`
+    pub filename_template: String,
+    /// Volume macOS packages are installed onto
+    pub macos_target: PathBuf,
+}
+
+impl SystemInfo {
+    /// The name SEGGER uses for `product`'s package of `version` (e.g. "V7.88a") on this
+    /// system.
+    pub fn package_filename(&self, product: &Product, version: &str) -> String {
+        self.package_filename_for_arch(product, version, &self.arch)
+    }
+
+    /// Candidate package names for `version`, starting with `arch` followed by the fallbacks.
+    pub fn package_filenames(&self, product: &Product, version: &str) -> Vec<String> {
+        std::iter::once(&self.arch)
+            .chain(&self.fallback_archs)
+            .map(|arch| self.package_filename_for_arch(product, version, arch))
+            .collect()
+    }
+
+    /// The name of `product`'s package of `version` for `arch` on this system, built from
+    /// `filename_template` by filling in `{product}` (e.g. "JLink"), `{system}`, `{version}`
+    /// (e.g. "V788a"), `{arch}` and `{ext}` (the package type). An empty `arch` gives the
+    /// name used by packages that aren't arch-specific, dropping the separator before it.
+    pub fn package_filename_for_arch(&self, product: &Product, version: &str, arch: &str) -> String {
+        let mut filename = self.filename_template.clone();
+        if arch.is_empty() {
+            filename = filename.replace("_{arch}", "").replace("-{arch}", "");
+        }
+        filename.replace("{product}", product.file_prefix)
+            .replace("{system}", &self.system)
+            .replace("{version}", &filename_version(version))
+            .replace("{arch}", arch)
+            .replace("{ext}", &self.package_type)
+    }
+}
+
+/// Returns the distribution identifiers from the `ID` and `ID_LIKE` fields of os-release.
+fn parse_os_release_ids(os_release: &str) -> Vec<String> {
+    os_release.lines()
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| *key == "ID" || *key == "ID_LIKE")
+        .flat_map(|(_, value)| value.trim_matches('"').split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>())
+        .collect()
+}
+
+fn linux_package_type(distro_ids: &[String]) -> &'static str {
+    let is_any = |names: &[&str]| distro_ids.iter().any(|id| names.contains(&id.as_str()));
+    if is_any(&["debian", "ubuntu"]) {
+        "deb"
+    } else if is_any(&["fedora", "rhel", "centos", "suse", "opensuse"]) {
+        "rpm"
+    } else {
+        "tgz"
+    }
+}
+
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Whether this process runs as root.
+#[cfg(unix)]
+pub fn is_root() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+pub fn is_root() -> bool {
+    false
+}
+
+/// Checks that `program` can be run as root: fails if it needs sudo but sudo isn't installed.
+pub fn check_sudo(command: &[String]) -> Result<()> {
+    if command.first().map(String::as_str) == Some("sudo") && find_in_path("sudo").is_none() {
+        return Err(Error::Install(format!(
+            "`{}` needs sudo, which isn't installed; run as root or pass --package-install-cmd",
+            command.join(" "))));
+    }
+    Ok(())
+}
+
+/// Picks an install command for the package type, preferring package managers that
+/// resolve dependencies unless `resolve_deps` is off. SEGGER doesn't ship a pacman package,
+/// so Arch uses the tarball.
+fn linux_install_cmd(package_type: &str, prefix: &Path, resolve_deps: bool) -> String {
+    let managers: &[(&str, &'static str)] = match package_type {
+        _ if !resolve_deps => &[],
+        "deb" => &[("apt-get", "sudo apt-get install -y")],
+        "rpm" => &[
+            ("dnf", "sudo dnf install -y"),
+            ("yum", "sudo yum install -y"),
+            ("zypper", "sudo zypper --non-interactive install"),
+        ],
+        _ => &[],
+    };
+
+    managers.iter()
+        .find(|(program, _)| find_in_path(program).is_some())
+        .map(|(_, cmd)| cmd.to_string())
+        .unwrap_or_else(|| match package_type {
+            "deb" => "sudo dpkg -i".to_string(),
+            "rpm" => "sudo rpm -U".to_string(),
+            // -p keeps the permissions from the tarball
+            _ => format!("sudo tar -C {} -xzpf", prefix.display()),
+        })
+}
+
+/// The package types SEGGER publishes for `system`.
+pub fn package_types_for_system(system: &str) -> &'static [&'static str] {
+    match system {
+        "Linux" => &["deb", "rpm", "tgz"],
+        "MacOSX" => &["pkg"],
+        "Windows" => &["exe", "msi"],
+        _ => &[],
+    }
+}
+
+/// Checks that `target` is a volume macOS packages can be installed onto.
+fn check_macos_target(target: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(target).map_err(|e| Error::InvalidOptions(format!(
+        "install target {} can't be used: {}", target.display(), e)))?;
+    if !metadata.is_dir() {
+        return Err(Error::InvalidOptions(format!("install target {} is not a directory",
+                                                 target.display())));
+    }
+    if is_read_only_mount(target) {
+        return Err(Error::InvalidOptions(format!("install target {} is on a read-only volume",
+                                                 target.display())));
+    }
+    Ok(())
+}
+
+/// Whether `path` is on a file system mounted read-only. Ownership isn't checked here since
+/// `installer` runs as root; it reports any other reason it can't write.
+#[cfg(unix)]
+fn is_read_only_mount(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else { return false };
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    unsafe {
+        libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) == 0
+            && stat.assume_init().f_flag & libc::ST_RDONLY != 0
+    }
+}
+
+#[cfg(not(unix))]
+fn is_read_only_mount(_path: &Path) -> bool {
+    false
+}
+
+/// Checks that `template` only uses known placeholders.
+fn check_filename_template(template: &str) -> Result<()> {
+    let placeholder = regex::Regex::new(r"\{([^}]*)\}").expect("valid regex");
+    for caps in placeholder.captures_iter(template) {
+        if !FILENAME_PLACEHOLDERS.contains(&&caps[1]) {
+            return Err(Error::InvalidOptions(format!(
+                "unknown placeholder {{{}}} in filename template; valid placeholders: {}",
+                &caps[1], FILENAME_PLACEHOLDERS.join(", "))));
+        }
+    }
+    Ok(())
+}
+
+/// Names SEGGER has given its universal macOS package's arch, most recent first.
+const MACOS_UNIVERSAL_ARCHS: &[&str] = &["universal", "universal2", ""];
+
+/// Architectures SEGGER publishes packages for on `system`, as used in package names.
+pub fn architectures(system: &str) -> &'static [&'static str] {
+    match system {
+        "Linux" => &["x86_64", "i386", "arm", "arm64"],
+        "MacOSX" => &["universal", "x86_64", "arm64"],
+        "Windows" => &["x86_64", "i386", "arm64"],
+        _ => &[],
+    }
+}
+
+fn package_type_or<'a>(options: &'a SystemOptions, default: &'a str) -> &'a str {
+    if options.package_type == "auto" {
+        default
+    } else {
+        options.package_type.as_str()
+    }
+}
+
+/// Maps an architecture name as reported by Rust, uname or a package manager onto the one
+/// SEGGER uses in package names: `arm` for 32-bit ARM (armhf) and `arm64` for 64-bit ARM.
+fn segger_arch(arch: &str) -> String {
+    let lower = arch.to_lowercase();
+    match lower.as_str() {
+        "aarch64" | "arm64" => "arm64",
+        "arm" | "armhf" | "armel" => "arm",
+        _ if lower.starts_with("armv") => "arm",
+        "amd64" | "x86_64" => "x86_64",
+        "x86" | "i386" | "i486" | "i586" | "i686" => "i386",
+        _ => arch,
+    }.to_string()
+}
+
+pub fn get_system_info(options: &SystemOptions) -> Result<SystemInfo> {
+    let system = if options.system == "auto" {
+        std::env::consts::OS
+    } else {
+        options.system.as_str()
+    };
+
+    let (arch, system, package_type, mut package_install_cmd) = match system {
+        "linux" | "Linux" => {
+            let arch = if options.arch == "auto" {
+                std::env::consts::ARCH.to_string()
+            } else {
+                options.arch.clone()
+            };
+
+            let package_type = if options.package_type == "auto" {
+                let os_release = std::fs::read_to_string("/etc/os-release").unwrap_or_default();
+                linux_package_type(&parse_os_release_ids(&os_release))
+            } else {
+                options.package_type.as_str()
+            };
+            (arch, "Linux", package_type, linux_install_cmd(package_type, &options.prefix, options.resolve_deps))
+        },
+        "macos" | "MacOSX" => {
+            let arch = if options.arch == "auto" {
+                std::env::consts::ARCH.to_string()
+            } else {
+                options.arch.clone()
+            };
+            check_macos_target(&options.macos_target)?;
+            // The target volume is added after the package, as its path may contain spaces
+            (arch, "MacOSX", package_type_or(options, "pkg"), "sudo installer -pkg".to_string())
+        },
+        "windows" | "Windows" => {
+            let arch = if options.arch == "auto" {
+                if cfg!(target_arch = "x86_64") {
+                    "x86_64"
+                } else {
+                    ""
+                }.to_string()
+            } else {
+                options.arch.clone()
+            };
+            (arch.to_owned(), "Windows", package_type_or(options, "exe"), String::new())
+        },
+        _ => return Err(Error::UnsupportedSystem(system.to_string())),
+    };
+
+    let valid_package_types = package_types_for_system(system);
+    if !valid_package_types.contains(&package_type) {
+        return Err(Error::InvalidOptions(format!(
+            "package type '{}' is not available for {}; valid package types: {}",
+            package_type, system, valid_package_types.join(", "))));
+    }
+
+    let arch = segger_arch(&arch);
+    // SEGGER doesn't build every architecture for every system, and asking for a package it
+    // doesn't build, like a Linux 'universal' one, would only fail with a 404 later on
+    let valid_archs = architectures(system);
+    if options.arch != "auto" && arch != "all" && !valid_archs.contains(&arch.as_str()) {
+        return Err(Error::InvalidOptions(format!(
+            "architecture '{}' is not available for {}; valid architectures: {}",
+            options.arch, system, valid_archs.join(", "))));
+    }
+
+    if options.package_install_cmd != "auto" {
+        package_install_cmd = options.package_install_cmd.clone();
+    } else if is_root() {
+        // Root doesn't need sudo, and minimal containers often don't have it
+        if let Some(command) = package_install_cmd.strip_prefix("sudo ") {
+            package_install_cmd = command.to_string();
+        }
+    }
+
+    // Not every release has arch-specific macOS packages, and the universal one has been
+    // named a few ways; older releases have a single package with no arch in its name
+    let fallback_archs: Vec<String> = match options.arch.as_str() {
+        _ if system != "MacOSX" => &[],
+        "universal" => &MACOS_UNIVERSAL_ARCHS[1..],
+        "auto" => MACOS_UNIVERSAL_ARCHS,
+        _ => &[],
+    }.iter().map(|arch| arch.to_string()).collect();
+
+    check_filename_template(&options.filename_template)?;
+
+    Ok(SystemInfo {
+        arch,
+        system: system.to_string(),
+        package_type: package_type.to_string(),
+        package_install_cmd,
+        fallback_archs,
+        prefix: options.prefix.clone(),
+        filename_template: options.filename_template.clone(),
+        macos_target: options.macos_target.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::JLINK;
+
+    #[test]
+    fn linux_distro_package_types() {
+        let ubuntu = "NAME=\"Ubuntu\"\nID=ubuntu\nID_LIKE=debian\n";
+        let opensuse = "ID=\"opensuse-tumbleweed\"\nID_LIKE=\"opensuse suse\"\n";
+        let arch = "ID=arch\n";
+        assert_eq!(linux_package_type(&parse_os_release_ids(ubuntu)), "deb");
+        assert_eq!(linux_package_type(&parse_os_release_ids(opensuse)), "rpm");
+        assert_eq!(linux_package_type(&parse_os_release_ids(arch)), "tgz");
+    }
+
+    #[test]
+    fn maps_architectures_to_segger_names() {
+        assert_eq!(segger_arch("aarch64"), "arm64");
+        assert_eq!(segger_arch("arm"), "arm");
+        assert_eq!(segger_arch("armv7l"), "arm");
+        assert_eq!(segger_arch("armv6l"), "arm");
+        assert_eq!(segger_arch("armv8l"), "arm");
+        assert_eq!(segger_arch("armhf"), "arm");
+        assert_eq!(segger_arch("amd64"), "x86_64");
+        assert_eq!(segger_arch("i686"), "i386");
+        assert_eq!(segger_arch("x86"), "i386");
+        assert_eq!(segger_arch("universal"), "universal");
+    }
+
+    #[test]
+    fn macos_package_name_variants() {
+        let options = SystemOptions {
+            system: "MacOSX".to_string(),
+            arch: "arm64".to_string(),
+            ..SystemOptions::default()
+        };
+        let explicit = get_system_info(&options).unwrap();
+        assert_eq!(explicit.package_filenames(&JLINK, "V7.94"), ["JLink_MacOSX_V794_arm64.pkg"]);
+
+        let universal = get_system_info(&SystemOptions {
+            arch: "universal".to_string(),
+            ..options.clone()
+        }).unwrap();
+        assert_eq!(universal.package_filenames(&JLINK, "V7.94"),
+                   ["JLink_MacOSX_V794_universal.pkg", "JLink_MacOSX_V794_universal2.pkg",
+                    "JLink_MacOSX_V794.pkg"]);
+
+        let missing_target = SystemOptions {
+            macos_target: PathBuf::from("/no/such/volume"),
+            ..options
+        };
+        assert!(matches!(get_system_info(&missing_target), Err(Error::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn filename_templates() {
+        let options = SystemOptions {
+            system: "Linux".to_string(),
+            arch: "arm64".to_string(),
+            package_type: "deb".to_string(),
+            ..SystemOptions::default()
+        };
+        let default = get_system_info(&options).unwrap();
+        assert_eq!(default.package_filename(&JLINK, "V7.88a"), "JLink_Linux_V788a_arm64.deb");
+
+        let custom = get_system_info(&SystemOptions {
+            filename_template: "{product}-{version}-{system}-{arch}.{ext}".to_string(),
+            ..options.clone()
+        }).unwrap();
+        assert_eq!(custom.package_filename(&JLINK, "V7.88a"), "JLink-V788a-Linux-arm64.deb");
+
+        let unknown = SystemOptions {
+            filename_template: "{product}_{release}.{ext}".to_string(),
+            ..options
+        };
+        assert!(matches!(get_system_info(&unknown), Err(Error::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn rejects_package_type_for_wrong_system() {
+        let options = SystemOptions {
+            system: "MacOSX".to_string(),
+            package_type: "deb".to_string(),
+            ..SystemOptions::default()
+        };
+        assert!(matches!(get_system_info(&options), Err(Error::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn rejects_architecture_for_wrong_system() {
+        for (system, arch) in [("Linux", "universal"), ("Windows", "universal"),
+                               ("Windows", "armhf"), ("MacOSX", "i386")] {
+            let options = SystemOptions {
+                system: system.to_string(),
+                arch: arch.to_string(),
+                package_type: String::from(if system == "Linux" { "deb" } else { "auto" }),
+                ..SystemOptions::default()
+            };
+            assert!(matches!(get_system_info(&options), Err(Error::InvalidOptions(_))),
+                    "{} {}", system, arch);
+        }
+        let options = SystemOptions {
+            system: "Linux".to_string(),
+            arch: "armhf".to_string(),
+            package_type: "deb".to_string(),
+            ..SystemOptions::default()
+        };
+        assert_eq!(get_system_info(&options).unwrap().arch, "arm");
+    }
+}
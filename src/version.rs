@@ -0,0 +1,258 @@
+use crate::error::{Error, Result};
+use regex::Regex;
+
+/// Converts a patch number to SEGGER's letter suffix: 1 -> "a", 26 -> "z", 27 -> "aa".
+pub fn patch_to_suffix(patch: i32) -> String {
+    let mut suffix = Vec::new();
+    let mut n = patch;
+    while n > 0 {
+        n -= 1;
+        suffix.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    suffix.reverse();
+    String::from_utf8(suffix).unwrap()
+}
+
+/// Converts SEGGER's letter suffix to a patch number: "a" -> 1, "aa" -> 27. Patches past 99
+/// ("cu") would spill into the minor digits of a version number, so they give None.
+fn suffix_to_patch(suffix: &str) -> Option<i32> {
+    let patch = suffix.bytes().try_fold(0i32, |acc, c| {
+        acc.checked_mul(26)?.checked_add((c - b'a' + 1) as i32)
+    })?;
+    (patch <= 99).then_some(patch)
+}
+
+pub fn version_number_to_string(version: i32) -> String {
+    let major = version / 10000;
+    let minor = (version / 100) % 100;
+    let patch = version % 100;
+    
+    format!("V{}.{:02}{}", major, minor, patch_to_suffix(patch))
+}
+
+/// Pre-release markers SEGGER appends to version strings, e.g. "V7.88 (beta)" or "V7.88a_rc1".
+const PRERELEASE_MARKERS: &str = r"(?i)(alpha|beta|preview|rc\d*)";
+
+/// Whether `version` is marked as a beta or release candidate rather than a release.
+pub fn is_prerelease(version: &str) -> bool {
+    Regex::new(PRERELEASE_MARKERS).map(|re| re.is_match(version)).unwrap_or(false)
+}
+
+/// Parses a version like "V7.88a" into its number, e.g. 78801. Anything after the patch
+/// letters, like " (beta)" or "_rc1", is ignored; see `is_prerelease`.
+pub fn version_string_to_number(version: &str) -> Option<i32> {
+    let re = Regex::new(r"[vV]?(\d+)\.(\d+)([a-z]*)(\d*)").ok()?;
+    let caps = re.captures(version)?;
+    
+    let major: i32 = caps.get(1)?.as_str().parse().ok()?;
+    // Like the patch, a minor past 99 would spill into the next field
+    let minor: i32 = caps.get(2)?.as_str().parse().ok().filter(|minor| *minor <= 99)?;
+    // Letters directly followed by digits ("rc1") or spelling a marker ("beta") are a
+    // pre-release tag, not a patch suffix
+    let letters = &caps[3];
+    let is_tag = !caps[4].is_empty() || is_prerelease(letters);
+    let patch = if is_tag { 0 } else { suffix_to_patch(letters)? };
+    
+    // A major too big for an i32 version number isn't a real release
+    major.checked_mul(10000)?.checked_add(minor.checked_mul(100)?)?.checked_add(patch)
+}
+
+/// Converts the value returned by the J-Link library's `JLINK_GetDLLVersion` to a version
+/// number. SEGGER documents it as `major * 10000 + minor * 100 + revision`, with revision 1
+/// for "a", which is the same layout `version_string_to_number` uses: V7.88a is 78801 in
+/// both. The layout has held from V4 to V8, but isn't assumed blindly: a value with more
+/// than six digits can't have a two-digit minor and revision after any real major, so it's
+/// taken to carry extra two-digit fields (like a build number) after the revision, which are
+/// dropped. Values without a major give None, so the caller falls back to other ways of
+/// finding the version. Any two-digit revision is accepted, like `version_string_to_number`
+/// does, so two-letter revisions like "aa" decode too.
+pub fn dll_version_to_number(dll_version: i32) -> Option<i32> {
+    let mut version = dll_version;
+    while version >= 1_000_000 {
+        version /= 100;
+    }
+    (version >= 10000).then_some(version)
+}
+
+/// The version as it appears in SEGGER's package names: "V7.88a" and "7.88a" both give
+/// "V788a". Pre-release labels like " (beta)" are only shown on the download page, so
+/// they're dropped.
+pub fn filename_version(version: &str) -> String {
+    match version_string_to_number(version) {
+        Some(number) => version_number_to_string(number).replace('.', ""),
+        None => version.split_whitespace().next().unwrap_or_default().replace('.', ""),
+    }
+}
+
+/// Reads the version from a package or install directory named the way SEGGER names them,
+/// e.g. "JLink_Linux_V788a_x86_64.deb" or "JLink_V788a".
+pub fn version_from_filename(filename: &str) -> Option<i32> {
+    let re = Regex::new(r"_[vV](\d+)(\d{2})([a-z]*)(_|$)").ok()?;
+    let caps = re.captures(filename)?;
+    version_string_to_number(&format!("V{}.{}{}", &caps[1], &caps[2], &caps[3]))
+}
+
+/// Picks the highest version offered for download, ignoring entries that don't parse.
+pub fn select_latest_version(available_versions: &[String]) -> Result<(String, i32)> {
+    available_versions.iter()
+        .filter_map(|v| version_string_to_number(v).map(|n| (v.clone(), n)))
+        .max_by_key(|(_, n)| *n)
+        .ok_or_else(|| Error::PageLayout("no recognizable versions in version dropdown".to_string()))
+}
+
+/// How many distinct versions in `available_versions` are newer than `installed` but not
+/// newer than `latest`.
+pub fn count_newer_versions(available_versions: &[String], installed: i32, latest: i32) -> usize {
+    let mut newer: Vec<i32> = available_versions.iter()
+        .filter_map(|v| version_string_to_number(v))
+        .filter(|n| *n > installed && *n <= latest)
+        .collect();
+    newer.sort_unstable();
+    newer.dedup();
+    newer.len()
+}
+
+/// Finds `target_version` among the versions offered for download, returning its display
+/// string and number.
+pub fn select_target_version(available_versions: &[String], target_version: &str)
+    -> Result<(String, i32)> {
+    let target_number = version_string_to_number(target_version)
+        .ok_or_else(|| Error::VersionParse(target_version.to_string()))?;
+
+    let mut parsed: Vec<(String, i32)> = available_versions.iter()
+        .filter_map(|v| version_string_to_number(v).map(|n| (v.clone(), n)))
+        .collect();
+
+    if let Some(found) = parsed.iter().find(|(_, n)| *n == target_number) {
+        return Ok(found.clone());
+    }
+
+    parsed.sort_by_key(|(_, n)| (n - target_number).abs());
+    Err(Error::VersionNotFound {
+        version: version_number_to_string(target_number),
+        closest: parsed.into_iter().take(5).map(|(v, _)| v).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patch_suffixes() {
+        assert_eq!(patch_to_suffix(0), "");
+        assert_eq!(patch_to_suffix(1), "a");
+        assert_eq!(patch_to_suffix(26), "z");
+        assert_eq!(patch_to_suffix(27), "aa");
+        assert_eq!(patch_to_suffix(28), "ab");
+    }
+
+    #[test]
+    fn version_round_trip() {
+        for patch in [0, 1, 26, 27] {
+            let number = 78800 + patch;
+            let string = version_number_to_string(number);
+            assert_eq!(version_string_to_number(&string), Some(number));
+        }
+        assert_eq!(version_number_to_string(78827), "V7.88aa");
+        assert_eq!(version_string_to_number("V7.88z"), Some(78826));
+    }
+
+    #[test]
+    fn rejects_patches_past_99() {
+        assert_eq!(version_string_to_number("V7.88cu"), Some(78899));
+        // "cv" is patch 100, which would read as V7.89
+        assert_eq!(version_string_to_number("V7.88cv"), None);
+        assert_eq!(version_string_to_number("V7.88dw"), None);
+        assert_eq!(version_string_to_number("V7.88zzzzzzzz"), None);
+    }
+
+    #[test]
+    fn multi_digit_major() {
+        assert_eq!(version_string_to_number("V10.00"), Some(100000));
+        assert_eq!(version_number_to_string(100000), "V10.00");
+        assert_eq!(version_string_to_number("V12.34b"), Some(123402));
+        assert_eq!(version_number_to_string(123402), "V12.34b");
+        assert_eq!(version_string_to_number("V999999.00"), None);
+        assert_eq!(version_string_to_number("V7.99999999"), None);
+        // 7 * 10000 + 100 * 100 would read as V8.00
+        assert_eq!(version_string_to_number("V7.100a"), None);
+        assert_eq!(version_string_to_number("V7.99a"), Some(79901));
+    }
+
+    #[test]
+    fn prerelease_versions() {
+        assert_eq!(version_string_to_number("V7.88 (beta)"), Some(78800));
+        assert_eq!(version_string_to_number("V7.88a_rc1"), Some(78801));
+        assert_eq!(version_string_to_number("V7.88rc1"), Some(78800));
+        assert_eq!(version_string_to_number("V7.88beta "), Some(78800));
+        assert_eq!(version_string_to_number("  V7.94b  "), Some(79402));
+        assert!(is_prerelease("V7.88 (beta)"));
+        assert!(is_prerelease("V7.88a_rc1"));
+        assert!(is_prerelease("V7.88 BETA"));
+        assert!(!is_prerelease("V7.88a"));
+        assert!(!is_prerelease("V7.94b"));
+    }
+
+    #[test]
+    fn dll_versions() {
+        // JLINK_GetDLLVersion results with the versions J-Link Commander showed alongside
+        for (dll_version, version) in [(78801, "V7.88a"), (79405, "V7.94e"), (49805, "V4.98e"),
+                                       (80000, "V8.00")] {
+            let number = dll_version_to_number(dll_version).unwrap();
+            assert_eq!(version_number_to_string(number), version);
+            assert_eq!(version_string_to_number(version), Some(number));
+        }
+        // A two-digit major, and a value with a build number after the revision
+        for (dll_version, version) in [(100000, "V10.00"), (7940500, "V7.94e"),
+                                       (794050012, "V7.94e")] {
+            let number = dll_version_to_number(dll_version).unwrap();
+            assert_eq!(version_number_to_string(number), version);
+        }
+        assert_eq!(dll_version_to_number(78827).map(version_number_to_string),
+                   Some("V7.88aa".to_string()));
+        assert_eq!(dll_version_to_number(78899), Some(78899));
+        assert_eq!(dll_version_to_number(0), None);
+        assert_eq!(dll_version_to_number(-79405), None);
+    }
+
+    #[test]
+    fn filename_versions() {
+        assert_eq!(filename_version("V7.94e"), "V794e");
+        assert_eq!(filename_version("7.88a"), "V788a");
+        assert_eq!(filename_version("v7.80"), "V780");
+        assert_eq!(filename_version("V10.00"), "V1000");
+        assert_eq!(filename_version("V7.88 (beta)"), "V788");
+        // Names of packages published on the download page
+        for filename in ["JLink_Linux_V794e_x86_64.deb", "JLink_MacOSX_V788a_universal.pkg",
+                         "JLink_Windows_V780_x86_64.exe"] {
+            let version = version_number_to_string(version_from_filename(filename).unwrap());
+            assert!(filename.contains(&format!("_{}_", filename_version(&version))));
+        }
+    }
+
+    #[test]
+    fn versions_from_filenames() {
+        assert_eq!(version_from_filename("JLink_Linux_V788a_x86_64.deb"), Some(78801));
+        assert_eq!(version_from_filename("JLink_MacOSX_V794_universal.pkg"), Some(79400));
+        assert_eq!(version_from_filename("JLink_V1000"), Some(100000));
+        assert_eq!(version_from_filename("jlink.deb"), None);
+    }
+
+    #[test]
+    fn counts_newer_versions() {
+        let available: Vec<String> = ["V7.96", "V7.94b", "V7.94a", "V7.94a", "V7.92", "V7.88"]
+            .iter().map(|s| s.to_string()).collect();
+        assert_eq!(count_newer_versions(&available, 79200, 79600), 3);
+        assert_eq!(count_newer_versions(&available, 79200, 79402), 2);
+        assert_eq!(count_newer_versions(&available, 79600, 79600), 0);
+    }
+
+    #[test]
+    fn latest_version_is_numeric_maximum() {
+        let available: Vec<String> = ["Beta", "V7.88", "V7.94b", "V7.94a"]
+            .iter().map(|s| s.to_string()).collect();
+        assert_eq!(select_latest_version(&available).unwrap(), ("V7.94b".to_string(), 79402));
+    }
+}
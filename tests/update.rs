@@ -0,0 +1,534 @@
+//! End-to-end runs of the binary against a mock SEGGER download page.
+#![cfg(unix)]
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const PAGE: &str = include_str!("fixtures/jlink_page.html");
+const PACKAGE: &str = "JLink_Linux_V9998_x86_64.deb";
+
+/// A scratch directory holding the run's config, cache, lock file and downloads, plus a fake
+/// `dpkg-query` that reports the installed J-Link version.
+struct Sandbox {
+    dir: PathBuf,
+}
+
+impl Sandbox {
+    fn new(name: &str, installed_version: Option<&str>) -> Self {
+        let dir = std::env::temp_dir()
+            .join(format!("jlinkupdate-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for sub in ["bin", "cache", "config", "tmp", "out"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+
+        let sandbox = Sandbox { dir };
+        match installed_version {
+            Some(version) => sandbox.script("dpkg-query", &format!("printf '{}'", version)),
+            None => sandbox.script("dpkg-query", "exit 1"),
+        }
+        std::fs::write(sandbox.dir.join("config.toml"), "install = false\n").unwrap();
+        sandbox
+    }
+
+    /// Puts a shell script called `name` on the binary's PATH, ahead of the real programs.
+    fn script(&self, name: &str, body: &str) {
+        let path = self.dir.join("bin").join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    fn out_dir(&self) -> PathBuf {
+        self.dir.join("out")
+    }
+
+    /// The binary with options for a Linux deb run in the sandbox, without a base URL.
+    fn command(&self) -> Command {
+        self.command_for_arch("x86_64")
+    }
+
+    fn command_for_arch(&self, arch: &str) -> Command {
+        let mut command = self.command_without_license(arch);
+        command.arg("--accept-license");
+        command
+    }
+
+    fn command_without_license(&self, arch: &str) -> Command {
+        let path = std::env::var("PATH").unwrap_or_default();
+        let mut command = Command::new(env!("CARGO_BIN_EXE_JLinkUpdate"));
+        command.args(["--system", "Linux", "--arch", arch, "--package-type", "deb",
+                      "--yes", "--quiet", "--refresh"])
+            .arg("--config").arg(self.dir.join("config.toml"))
+            .arg("--output-dir").arg(self.out_dir())
+            .env_remove("JLINK_BASE_URL")
+            .env("PATH", format!("{}:{}", self.dir.join("bin").display(), path))
+            .env("XDG_CACHE_HOME", self.dir.join("cache"))
+            .env("XDG_CONFIG_HOME", self.dir.join("config"))
+            .env("TMPDIR", self.dir.join("tmp"));
+        command
+    }
+
+    fn run(&self, base_url: &str) -> Output {
+        self.command().arg("--base-url").arg(base_url).output().unwrap()
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn read(path: &Path) -> String {
+    std::fs::read_to_string(path).unwrap_or_default()
+}
+
+#[test]
+fn downloads_latest_package() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    let package = server.mock("POST", format!("/{}", PACKAGE).as_str())
+        .match_body("accept_license_agreement=accepted")
+        .with_body("fake installer")
+        .create();
+
+    let sandbox = Sandbox::new("download", None);
+    let output = sandbox.run(&server.url());
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    package.assert();
+    assert_eq!(read(&sandbox.out_dir().join(PACKAGE)), "fake installer");
+    // Without installing, the downloaded package's path is printed for scripts
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(),
+               sandbox.out_dir().join(PACKAGE).display().to_string());
+}
+
+#[test]
+fn reads_base_url_from_environment() {
+    let mut server = mockito::Server::new();
+    let page = server.mock("GET", "/").with_body(PAGE).create();
+    server.mock("POST", format!("/{}", PACKAGE).as_str()).with_body("fake installer").create();
+
+    let sandbox = Sandbox::new("env", None);
+    let output = sandbox.command().env("JLINK_BASE_URL", server.url()).output().unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    page.assert();
+}
+
+#[test]
+fn skips_download_when_up_to_date() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    let package = server.mock("POST", format!("/{}", PACKAGE).as_str())
+        .expect(0)
+        .create();
+
+    let sandbox = Sandbox::new("up-to-date", Some("99.98"));
+    let output = sandbox.run(&server.url());
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    package.assert();
+    assert!(!sandbox.out_dir().join(PACKAGE).exists());
+}
+
+#[test]
+fn fails_when_package_is_missing() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    let package = server.mock("POST", format!("/{}", PACKAGE).as_str())
+        .with_status(404)
+        .with_body("<html><body><p>File not found for this architecture</p></body></html>")
+        .create();
+
+    let sandbox = Sandbox::new("missing", None);
+    let output = sandbox.run(&server.url());
+
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("404"));
+    // The server's explanation is passed on, without the markup
+    assert!(stderr.contains(": File not found for this architecture"), "{}", stderr);
+    package.assert();
+    assert!(!sandbox.out_dir().join(PACKAGE).exists());
+}
+
+#[test]
+fn falls_back_to_mirror() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    server.mock("POST", format!("/{}", PACKAGE).as_str()).with_status(404).create();
+    let mut mirror = mockito::Server::new();
+    let package = mirror.mock("POST", format!("/{}", PACKAGE).as_str())
+        .with_body("mirrored installer")
+        .create();
+
+    let sandbox = Sandbox::new("mirror", None);
+    let output = sandbox.command()
+        .arg("--base-url").arg(server.url())
+        .arg("--mirror").arg(mirror.url())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    package.assert();
+    assert_eq!(read(&sandbox.out_dir().join(PACKAGE)), "mirrored installer");
+}
+
+#[test]
+fn check_only_exits_with_update_available() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+
+    let sandbox = Sandbox::new("check-only", Some("99.90a"));
+    let output = sandbox.command()
+        .arg("--base-url").arg(server.url())
+        .arg("--check-only")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("V99.98"));
+}
+
+#[test]
+fn downloads_every_architecture() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    for (arch, status) in [("x86_64", 200), ("i386", 404), ("arm", 404), ("arm64", 200)] {
+        server.mock("POST", format!("/JLink_Linux_V9998_{}.deb", arch).as_str())
+            .with_status(status)
+            .with_body(arch)
+            .create();
+    }
+
+    let sandbox = Sandbox::new("all-archs", Some("99.98"));
+    let output = sandbox.command_for_arch("all")
+        .arg("--base-url").arg(server.url())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("i386: not found"));
+    assert_eq!(read(&sandbox.out_dir().join("JLink_Linux_V9998_x86_64.deb")), "x86_64");
+    assert_eq!(read(&sandbox.out_dir().join("JLink_Linux_V9998_arm64.deb")), "arm64");
+}
+
+#[test]
+fn prints_every_architecture_url() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    let packages = server.mock("POST", mockito::Matcher::Any).expect(0).create();
+
+    let sandbox = Sandbox::new("all-archs-url", None);
+    let output = sandbox.command_for_arch("all")
+        .arg("--base-url").arg(server.url())
+        .arg("--print-url")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    packages.assert();
+    let urls: Vec<String> = ["x86_64", "i386", "arm", "arm64"].iter()
+        .map(|arch| format!("{}/JLink_Linux_V9998_{}.deb", server.url(), arch))
+        .collect();
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().collect::<Vec<_>>(), urls);
+}
+
+#[test]
+fn rejects_check_only_for_every_architecture() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    let packages = server.mock("POST", mockito::Matcher::Any).expect(0).create();
+
+    let sandbox = Sandbox::new("all-archs-check", Some("99.90a"));
+    let output = sandbox.command_for_arch("all")
+        .arg("--base-url").arg(server.url())
+        .arg("--check-only")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    packages.assert();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--arch all can't be used with \
+                                                               --check-only"));
+}
+
+#[test]
+fn skips_beta_unless_asked() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+
+    let sandbox = Sandbox::new("beta", None);
+    let print_url = |extra: &[&str]| {
+        let output = sandbox.command()
+            .arg("--base-url").arg(server.url())
+            .arg("--print-url")
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    // Only the URL goes to stdout, so it can be piped straight into another tool
+    assert_eq!(print_url(&[]).trim(), format!("{}/{}", server.url(), PACKAGE));
+    assert!(print_url(&["--include-beta"]).contains("JLink_Linux_V9999_x86_64.deb"));
+}
+
+#[test]
+fn requires_license_acceptance() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    let package = server.mock("POST", format!("/{}", PACKAGE).as_str())
+        .expect(0)
+        .create();
+
+    let sandbox = Sandbox::new("license", None);
+    let output = sandbox.command_without_license("x86_64")
+        .arg("--base-url").arg(server.url())
+        .stdin(std::process::Stdio::null())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--accept-license"));
+    package.assert();
+}
+
+#[test]
+fn reports_how_many_versions_behind() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+
+    let sandbox = Sandbox::new("behind", Some("99.90a"));
+    let output = sandbox.command()
+        .arg("--base-url").arg(server.url())
+        .args(["--check-only", "--format", "json"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2), "{}", String::from_utf8_lossy(&output.stderr));
+    // V99.90b and V99.98; the beta isn't counted
+    assert!(String::from_utf8_lossy(&output.stdout).contains("\"newer_versions\":2"));
+}
+
+#[test]
+fn reuses_cached_package() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    let package = server.mock("POST", format!("/{}", PACKAGE).as_str())
+        .with_body("fake installer")
+        .expect(1)
+        .create();
+
+    let sandbox = Sandbox::new("package-cache", None);
+    for _ in 0..2 {
+        let output = sandbox.run(&server.url());
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(read(&sandbox.out_dir().join(PACKAGE)), "fake installer");
+        std::fs::remove_file(sandbox.out_dir().join(PACKAGE)).unwrap();
+    }
+    package.assert();
+}
+
+#[test]
+fn subcommands_select_what_to_do() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+
+    let sandbox = Sandbox::new("subcommands", Some("99.90a"));
+    // Options work before and after the subcommand
+    let run = |subcommand: &str| sandbox.command()
+        .arg(subcommand)
+        .arg("--base-url").arg(server.url())
+        .output()
+        .unwrap();
+
+    let output = run("url");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(),
+               format!("{}/{}", server.url(), PACKAGE));
+
+    let output = run("check");
+    assert_eq!(output.status.code(), Some(2), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let output = run("list");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("V99.90b"));
+}
+
+#[test]
+fn downloads_anyway_when_ignoring_installed() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    let package = server.mock("POST", format!("/{}", PACKAGE).as_str())
+        .with_body("fake installer")
+        .create();
+
+    let sandbox = Sandbox::new("ignore-installed", Some("99.98"));
+    let output = sandbox.command()
+        .arg("--base-url").arg(server.url())
+        .arg("--ignore-installed")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    package.assert();
+    assert_eq!(read(&sandbox.out_dir().join(PACKAGE)), "fake installer");
+}
+
+#[test]
+fn generates_completions_and_man_pages() {
+    let sandbox = Sandbox::new("generate", None);
+    for what in ["bash", "man"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_JLinkUpdate"))
+            .args(["--generate", what, "--out-dir"]).arg(sandbox.out_dir())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+    assert!(sandbox.out_dir().join("JLinkUpdate.bash").is_file());
+    assert!(read(&sandbox.out_dir().join("JLinkUpdate.1")).contains(r"\-\-target\-version"));
+    assert!(sandbox.out_dir().join("JLinkUpdate-check.1").is_file());
+}
+
+#[test]
+fn verifies_existing_package() {
+    let mut server = mockito::Server::new();
+    server.mock("POST", format!("/{}", PACKAGE).as_str()).with_body("fake installer").create();
+
+    let sandbox = Sandbox::new("verify-only", None);
+    let package = sandbox.out_dir().join(PACKAGE);
+    std::fs::write(&package, "fake installer").unwrap();
+    let verify = |extra: &[&str]| sandbox.command()
+        .arg("--base-url").arg(server.url())
+        .arg("--verify-only").arg(&package)
+        .args(extra)
+        .output()
+        .unwrap();
+
+    // SHA256 of "fake installer"
+    let sha256 = "941ef2fd249e8e3535908e3663515a85a291c538016f75be86032da473029b3e";
+    let output = verify(&["--sha256", sha256]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = verify(&["--sha256", &"0".repeat(64)]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("SHA256 mismatch"));
+
+    // Without a digest, the size the server reports is checked
+    let output = verify(&[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    std::fs::write(&package, "truncated").unwrap();
+    let output = verify(&[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("should be 14 bytes"));
+}
+
+#[test]
+fn fails_when_dpkg_fails_for_more_than_dependencies() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    server.mock("POST", format!("/{}", PACKAGE).as_str()).with_body("fake installer").create();
+
+    let install = |package_status: &str| {
+        let sandbox = Sandbox::new("dpkg-failure", None);
+        sandbox.script("dpkg", "exit 1");
+        sandbox.script("apt-get", "exit 0");
+        sandbox.script("dpkg-deb", "echo jlink");
+        sandbox.script("dpkg-query", &format!("printf '{}'", package_status));
+        sandbox.command()
+            .arg("--base-url").arg(server.url())
+            .args(["--install", "--ignore-installed", "--no-backup"])
+            .args(["--package-install-cmd", "dpkg -i"])
+            .output()
+            .unwrap()
+    };
+
+    // Nothing left for apt-get to fix doesn't mean dpkg's failure went away
+    let output = install("deinstall ok config-files");
+    assert_eq!(output.status.code(), Some(4), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = install("install ok installed");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn leaves_dependencies_alone_without_resolving() {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    server.mock("POST", format!("/{}", PACKAGE).as_str()).with_body("fake installer").create();
+
+    let install = |extra: &[&str]| {
+        let sandbox = Sandbox::new("no-resolve-deps", None);
+        let log = sandbox.dir.join("commands.log");
+        sandbox.script("dpkg", &format!("echo dpkg \"$@\" >> '{}'", log.display()));
+        sandbox.script("apt-get", &format!("echo apt-get \"$@\" >> '{}'", log.display()));
+        let output = sandbox.command()
+            .arg("--base-url").arg(server.url())
+            .args(["--install", "--ignore-installed", "--no-backup"])
+            .args(["--package-install-cmd", "dpkg -i"])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        read(&log)
+    };
+
+    let commands = install(&["--no-resolve-deps"]);
+    assert!(commands.starts_with("dpkg -i "), "{}", commands);
+    assert!(!commands.contains("apt-get"), "{}", commands);
+    assert!(install(&[]).contains("apt-get install -f -y"));
+}
+
+/// Installs through a fake dnf with `installed` as the installed J-Link version, which becomes
+/// `installs` once dnf runs, and returns the arguments dnf was run with.
+fn dnf_arguments(name: &str, installed: &str, installs: &str, args: &[&str]) -> String {
+    let mut server = mockito::Server::new();
+    server.mock("GET", "/").with_body(PAGE).create();
+    server.mock("POST", mockito::Matcher::Regex(r"^/JLink_Linux_\w+_x86_64\.deb$".to_string()))
+        .with_body("fake installer")
+        .create();
+
+    let sandbox = Sandbox::new(name, None);
+    let version = sandbox.dir.join("version");
+    let log = sandbox.dir.join("dnf.log");
+    std::fs::write(&version, installed).unwrap();
+    sandbox.script("dpkg-query", &format!("cat '{}'", version.display()));
+    sandbox.script("dnf", &format!("echo \"$@\" > '{}'\nprintf '{}' > '{}'",
+                                   log.display(), installs, version.display()));
+    let output = sandbox.command()
+        .arg("--base-url").arg(server.url())
+        .args(["--install", "--no-backup", "--package-install-cmd", "dnf install -y"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    read(&log)
+}
+
+#[test]
+fn installs_normally_when_ignoring_installed() {
+    let arguments = dnf_arguments("dnf-ignore-installed", "99.98", "99.98",
+                                  &["--ignore-installed"]);
+    assert!(arguments.starts_with("install -y "), "{}", arguments);
+}
+
+#[test]
+fn reinstalls_only_the_installed_version() {
+    let arguments = dnf_arguments("dnf-reinstall", "99.98", "99.98", &["--force"]);
+    assert!(arguments.starts_with("reinstall -y "), "{}", arguments);
+    let arguments = dnf_arguments("dnf-force-update", "99.90b", "99.98", &["--force"]);
+    assert!(arguments.starts_with("install -y "), "{}", arguments);
+}
+
+#[test]
+fn asks_package_manager_to_downgrade() {
+    let downgrade = ["--target-version", "V99.90b", "--allow-downgrade"];
+    let arguments = dnf_arguments("dnf-downgrade", "99.98", "99.90b", &downgrade);
+    assert!(arguments.starts_with("downgrade -y "), "{}", arguments);
+    assert!(arguments.trim_end().ends_with("JLink_Linux_V9990b_x86_64.deb"), "{}", arguments);
+    let arguments = dnf_arguments("dnf-force-downgrade", "99.98", "99.90b",
+                                  &[&downgrade[..], &["--force"]].concat());
+    assert!(arguments.starts_with("downgrade -y "), "{}", arguments);
+}